@@ -0,0 +1,67 @@
+use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Deps, to_json_binary};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cw2::set_contract_version;
+use crate::error::ContractError;
+use crate::execute::{create_pair, instantiate_pool, INSTANTIATE_LP_TOKEN_REPLY_ID, INSTANTIATE_POOL_REPLY_ID, register_pair};
+use crate::msg::{Config, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::query::{query_config, query_pair, query_pairs};
+use crate::state::CONFIG;
+
+const CONTRACT_NAME: &str = "amm-factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError>  {
+    set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
+
+    CONFIG.save(deps.storage, &Config {
+        owner: info.sender,
+        pool_code_id: msg.pool_code_id,
+        lp_token_code_id: msg.lp_token_code_id,
+        fee_share: msg.fee_share,
+        tolerance_percentage: msg.tolerance_percentage,
+        dynamic_fee: msg.dynamic_fee,
+        max_referral_commission: msg.max_referral_commission,
+    })?;
+
+    Ok(Response::new().add_attribute("method", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError>  {
+    match msg {
+        ExecuteMsg::CreatePair {
+            asset_infos,
+            initial_lp_token_supply,
+        } => create_pair(deps, env, info, asset_infos, initial_lp_token_supply),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        INSTANTIATE_LP_TOKEN_REPLY_ID => instantiate_pool(deps, msg),
+        INSTANTIATE_POOL_REPLY_ID => register_pair(deps, msg),
+        _ => Err(ContractError::UnknownReplyId {}),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Pair { asset_infos } => to_json_binary(&query_pair(deps, asset_infos)?),
+        QueryMsg::Pairs { start_after, limit } => to_json_binary(&query_pairs(deps, start_after, limit)?),
+    }
+}
@@ -0,0 +1,27 @@
+use cosmwasm_std::StdError;
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Invalid funds sent")]
+    InvalidFunds {},
+
+    #[error("A pair needs two different assets")]
+    InvalidAssetInfos {},
+
+    #[error("Invalid zero amount")]
+    InvalidZeroAmount {},
+
+    #[error("Pair already exists")]
+    PairExists {},
+
+    #[error("Unknown reply id")]
+    UnknownReplyId {},
+}
@@ -0,0 +1,134 @@
+use amm_contract::msg::{Config as PoolConfig, InstantiateMsg as PoolInstantiateMsg, PoolType};
+use cosmwasm_std::{Coin, DepsMut, Env, MessageInfo, Reply, Response, StdError, SubMsg, to_json_binary, Uint128, WasmMsg};
+use cw20::{Cw20Coin, Cw20ExecuteMsg, MinterResponse};
+use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw_utils::parse_reply_instantiate_data;
+use crate::error::ContractError;
+use crate::state::{CONFIG, pair_key, PAIRS, PENDING_PAIR, PendingPair};
+
+pub const INSTANTIATE_POOL_REPLY_ID: u64 = 1;
+pub const INSTANTIATE_LP_TOKEN_REPLY_ID: u64 = 2;
+
+// Creating a pair takes three steps: the LP token is instantiated with the factory as its minter, then the pool
+// with that LP token, and once the pool is registered it takes over as the LP token's minter
+pub fn create_pair(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset_infos: [String; 2],
+    initial_lp_token_supply: Uint128,
+) -> Result<Response, ContractError> {
+    if asset_infos[0] == asset_infos[1] {
+        return Err(ContractError::InvalidAssetInfos {});
+    }
+    if initial_lp_token_supply.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    // Only one pool per pair, whichever order the assets are given in
+    let key = pair_key(&asset_infos);
+    if PAIRS.has(deps.storage, (&key[0], &key[1])) {
+        return Err(ContractError::PairExists {});
+    }
+
+    // The funds sent are the initial funding of the pool, one coin of each asset
+    if info.funds.len() != 2 {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let initial_funding = key
+        .iter()
+        .map(|denom| info.funds.iter().find(|coin| coin.denom == *denom).cloned())
+        .collect::<Option<Vec<Coin>>>()
+        .ok_or(ContractError::InvalidFunds {})?;
+
+    // The creator funds the pool, so they hold the whole initial supply
+    let config = CONFIG.load(deps.storage)?;
+    let lp_token_msg = Cw20InstantiateMsg {
+        name: "AMM LP token".to_string(),
+        symbol: "AMMLP".to_string(),
+        decimals: 6,
+        initial_balances: vec![Cw20Coin { address: info.sender.to_string(), amount: initial_lp_token_supply }],
+        mint: Some(MinterResponse { minter: env.contract.address.to_string(), cap: None }),
+        marketing: None,
+    };
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(config.owner.to_string()),
+        code_id: config.lp_token_code_id,
+        msg: to_json_binary(&lp_token_msg)?,
+        funds: vec![],
+        label: format!("{}-{} LP token", key[0], key[1]),
+    };
+    PENDING_PAIR.save(deps.storage, &PendingPair {
+        asset_infos: key.clone(),
+        initial_funding,
+        initial_lp_token_supply,
+        lp_token_addr: None,
+    })?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(instantiate_msg, INSTANTIATE_LP_TOKEN_REPLY_ID))
+        .add_attribute("action", "create_pair")
+        .add_attribute("pair", format!("{}-{}", key[0], key[1])))
+}
+
+// Instantiate the pool of the pending pair with its newly instantiated LP token
+pub fn instantiate_pool(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    let mut pending = PENDING_PAIR.load(deps.storage)?;
+    let instantiate_response = parse_reply_instantiate_data(reply)?;
+    let lp_token_addr = deps.api.addr_validate(&instantiate_response.contract_address)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let pool_msg = PoolInstantiateMsg {
+        initial_funding: pending.initial_funding.clone(),
+        initial_lp_token_supply: pending.initial_lp_token_supply,
+        owner: Some(config.owner.to_string()),
+        contract_config: PoolConfig {
+            lp_token_addr: lp_token_addr.to_string(),
+            fee_share: config.fee_share,
+            tolerance_percentage: config.tolerance_percentage,
+            pool_type: PoolType::ConstantProduct,
+            position_nft_addr: None,
+            dynamic_fee: config.dynamic_fee,
+            max_referral_commission: config.max_referral_commission,
+        },
+    };
+
+    let instantiate_msg = WasmMsg::Instantiate {
+        admin: Some(config.owner.to_string()),
+        code_id: config.pool_code_id,
+        msg: to_json_binary(&pool_msg)?,
+        funds: pending.initial_funding.clone(),
+        label: format!("{}-{} pair", pending.asset_infos[0], pending.asset_infos[1]),
+    };
+    pending.lp_token_addr = Some(lp_token_addr.clone());
+    PENDING_PAIR.save(deps.storage, &pending)?;
+
+    Ok(Response::new()
+        .add_submessage(SubMsg::reply_on_success(instantiate_msg, INSTANTIATE_POOL_REPLY_ID))
+        .add_attribute("action", "instantiate_pool")
+        .add_attribute("lp_token_addr", lp_token_addr))
+}
+
+// Register the address of the newly instantiated pool under its pair and make it the LP token's minter
+pub fn register_pair(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    let pending = PENDING_PAIR.load(deps.storage)?;
+    PENDING_PAIR.remove(deps.storage);
+    let key = pending.asset_infos;
+
+    let instantiate_response = parse_reply_instantiate_data(reply)?;
+    let pool_addr = deps.api.addr_validate(&instantiate_response.contract_address)?;
+    PAIRS.save(deps.storage, (&key[0], &key[1]), &pool_addr)?;
+
+    let lp_token_addr = pending.lp_token_addr.ok_or_else(|| StdError::generic_err("LP token of the pending pair not instantiated"))?;
+    let update_minter_msg = WasmMsg::Execute {
+        contract_addr: lp_token_addr.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::UpdateMinter { new_minter: Some(pool_addr.to_string()) })?,
+        funds: vec![],
+    };
+
+    Ok(Response::new()
+        .add_message(update_minter_msg)
+        .add_attribute("action", "register_pair")
+        .add_attribute("pool_addr", pool_addr)
+        .add_attribute("lp_token_addr", lp_token_addr))
+}
@@ -0,0 +1,62 @@
+use amm_contract::msg::DynamicFee;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Uint128};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Code id of the AMM pool contract
+    pub pool_code_id: u64,
+    /// Code id of the cw20 contract instantiated as the LP token of each pool
+    pub lp_token_code_id: u64,
+    /// Swap fee of new pools
+    pub fee_share: Decimal,
+    /// Deposit ratio tolerance of new pools
+    pub tolerance_percentage: Decimal,
+    /// Dynamic swap fee of new pools, none for the fixed fee share
+    pub dynamic_fee: Option<DynamicFee>,
+    /// Highest referral commission of new pools, zero disables referrals
+    pub max_referral_commission: Decimal,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Instantiate a constant product pool for the asset pair with the default fees, and its LP token.
+    /// The initial funding of both assets is sent with the message, and the sender receives the
+    /// initial LP token supply.
+    CreatePair {
+        asset_infos: [String; 2],
+        initial_lp_token_supply: Uint128,
+    },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    GetConfig {},
+    /// The pool of an asset pair, in either asset order
+    Pair { asset_infos: [String; 2] },
+    /// Registered pairs ordered by their sorted asset infos
+    Pairs { start_after: Option<[String; 2]>, limit: Option<u32> },
+}
+
+#[cw_serde]
+pub struct Config {
+    pub owner: Addr,
+    pub pool_code_id: u64,
+    pub lp_token_code_id: u64,
+    pub fee_share: Decimal,
+    pub tolerance_percentage: Decimal,
+    pub dynamic_fee: Option<DynamicFee>,
+    pub max_referral_commission: Decimal,
+}
+
+#[cw_serde]
+pub struct PairResponse {
+    /// Sorted asset infos of the pair
+    pub asset_infos: [String; 2],
+    pub contract_addr: Addr,
+}
+
+#[cw_serde]
+pub struct PairsResponse {
+    pub pairs: Vec<PairResponse>,
+}
@@ -0,0 +1,41 @@
+use cosmwasm_std::{Deps, Order, StdResult};
+use cw_storage_plus::Bound;
+use crate::msg::{Config, PairResponse, PairsResponse};
+use crate::state::{CONFIG, pair_key, PAIRS};
+
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
+
+pub fn query_config(deps: Deps) -> StdResult<Config> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(config)
+}
+
+pub fn query_pair(deps: Deps, asset_infos: [String; 2]) -> StdResult<PairResponse> {
+    let key = pair_key(&asset_infos);
+    let contract_addr = PAIRS.load(deps.storage, (&key[0], &key[1]))?;
+    Ok(PairResponse {
+        asset_infos: key,
+        contract_addr,
+    })
+}
+
+pub fn query_pairs(deps: Deps, start_after: Option<[String; 2]>, limit: Option<u32>) -> StdResult<PairsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_key = start_after.map(|asset_infos| pair_key(&asset_infos));
+    let start = start_key.as_ref().map(|key| Bound::exclusive((key[0].as_str(), key[1].as_str())));
+
+    let pairs = PAIRS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            let ((asset1, asset2), contract_addr) = item?;
+            Ok(PairResponse {
+                asset_infos: [asset1, asset2],
+                contract_addr,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PairsResponse { pairs })
+}
@@ -0,0 +1,30 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw_storage_plus::{Item, Map};
+use crate::msg::Config;
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// Pool address per pair, keyed by the sorted asset infos
+pub const PAIRS: Map<(&str, &str), Addr> = Map::new("pairs");
+
+// Pair whose LP token and pool are being instantiated, read back in the replies
+pub const PENDING_PAIR: Item<PendingPair> = Item::new("pending_pair");
+
+#[cw_serde]
+pub struct PendingPair {
+    /// Sorted asset infos of the pair
+    pub asset_infos: [String; 2],
+    /// Initial funding of the pool in the order of the asset infos
+    pub initial_funding: Vec<Coin>,
+    pub initial_lp_token_supply: Uint128,
+    /// Set once the LP token is instantiated
+    pub lp_token_addr: Option<Addr>,
+}
+
+// Asset infos in the order used as the pair key
+pub fn pair_key(asset_infos: &[String; 2]) -> [String; 2] {
+    let mut sorted = asset_infos.clone();
+    sorted.sort();
+    sorted
+}
@@ -0,0 +1,165 @@
+use cosmwasm_std::{Addr, coin, Decimal, Uint128};
+use cw20::{BalanceResponse, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use cw_multi_test::error::AnyResult;
+use amm_contract::msg::{Config as PoolConfig, DynamicFee, OwnerResponse, PoolType, ReservesResponse};
+use amm_contract::msg::QueryMsg::{GetConfig as GetPoolConfig, GetReserves, Owner as GetPoolOwner};
+use amm_factory::msg::{ExecuteMsg, InstantiateMsg, PairResponse, PairsResponse, QueryMsg};
+
+fn setup_factory(app: &mut App) -> Addr {
+    let pool_code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let pool_code_id = app.store_code(Box::new(pool_code));
+
+    let lp_token_code = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    let lp_token_code_id = app.store_code(Box::new(lp_token_code));
+
+    let factory_code = ContractWrapper::new(
+        amm_factory::contract::execute,
+        amm_factory::contract::instantiate,
+        amm_factory::contract::query,
+    )
+        .with_reply(amm_factory::contract::reply);
+    let factory_code_id = app.store_code(Box::new(factory_code));
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "creator".to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2"), coin(100000000,"asset3")],
+    }))
+        .unwrap();
+
+    app.instantiate_contract(
+        factory_code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            pool_code_id,
+            lp_token_code_id,
+            fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+            tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+            dynamic_fee: Some(DynamicFee {
+                min_fee: Decimal::permille(1),
+                max_fee: Decimal::percent(1),
+                window: 3600,
+                max_volatility: Decimal::percent(10),
+            }),
+            max_referral_commission: Decimal::percent(1),
+        },
+        &[],
+        "mock-amm-factory",
+        None,
+    ).unwrap()
+}
+
+fn create_pair(app: &mut App, factory: &Addr, denoms: [&str; 2]) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked("creator"),
+        factory.clone(),
+        &ExecuteMsg::CreatePair {
+            asset_infos: [denoms[0].to_string(), denoms[1].to_string()],
+            initial_lp_token_supply: Uint128::new(100),
+        },
+        &[coin(100000, denoms[0]), coin(200000, denoms[1])],
+    )
+}
+
+#[test]
+fn create_pair_instantiates_and_registers_pool() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    create_pair(&mut app, &factory, ["asset2", "asset1"]).unwrap();
+
+    // The pair is found in either asset order
+    let pair: PairResponse = app.wrap().query_wasm_smart(factory.clone(), &QueryMsg::Pair {
+        asset_infos: ["asset1".to_string(), "asset2".to_string()],
+    }).unwrap();
+    assert_eq!(pair.asset_infos, ["asset1".to_string(), "asset2".to_string()]);
+
+    // The pool is funded with the coins sent and uses the factory fees
+    let reserves: ReservesResponse = app.wrap().query_wasm_smart(pair.contract_addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(reserves.reserves, vec![coin(200000,"asset1"), coin(100000,"asset2")]);
+
+    let config: PoolConfig = app.wrap().query_wasm_smart(pair.contract_addr.clone(), &GetPoolConfig {}).unwrap();
+    assert_eq!(config.fee_share, Decimal::permille(3));
+    assert_eq!(config.pool_type, PoolType::ConstantProduct);
+    assert_eq!(config.dynamic_fee.map(|dynamic_fee| dynamic_fee.max_fee), Some(Decimal::percent(1)));
+    assert_eq!(config.max_referral_commission, Decimal::percent(1));
+
+    // The factory owner owns the pool
+    let owner: OwnerResponse = app.wrap().query_wasm_smart(pair.contract_addr.clone(), &GetPoolOwner {}).unwrap();
+    assert_eq!(owner.owner, Some(Addr::unchecked("owner")));
+
+    // The factory instantiated the LP token, the creator holds the initial supply and the pool mints the rest
+    let balance: BalanceResponse = app.wrap().query_wasm_smart(&config.lp_token_addr, &Cw20QueryMsg::Balance { address: "creator".to_string() }).unwrap();
+    assert_eq!(balance.balance, Uint128::new(100));
+    let minter: MinterResponse = app.wrap().query_wasm_smart(&config.lp_token_addr, &Cw20QueryMsg::Minter {}).unwrap();
+    assert_eq!(minter.minter, pair.contract_addr.to_string());
+
+    let contract_info = app.wrap().query_wasm_contract_info(pair.contract_addr).unwrap();
+    assert_eq!(contract_info.admin, Some("owner".to_string()));
+}
+
+#[test]
+fn create_pair_rejects_duplicates_and_invalid_pairs() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    create_pair(&mut app, &factory, ["asset1", "asset2"]).unwrap();
+    create_pair(&mut app, &factory, ["asset2", "asset1"]).unwrap_err();
+
+    // Both assets must differ
+    app.execute_contract(
+        Addr::unchecked("creator"),
+        factory.clone(),
+        &ExecuteMsg::CreatePair {
+            asset_infos: ["asset3".to_string(), "asset3".to_string()],
+            initial_lp_token_supply: Uint128::new(100),
+        },
+        &[coin(100000,"asset3")],
+    ).unwrap_err();
+
+    // The initial funding has to match the pair
+    app.execute_contract(
+        Addr::unchecked("creator"),
+        factory.clone(),
+        &ExecuteMsg::CreatePair {
+            asset_infos: ["asset1".to_string(), "asset3".to_string()],
+            initial_lp_token_supply: Uint128::new(100),
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+    ).unwrap_err();
+}
+
+#[test]
+fn pairs_are_paginated() {
+    let mut app = App::default();
+    let factory = setup_factory(&mut app);
+
+    create_pair(&mut app, &factory, ["asset2", "asset3"]).unwrap();
+    create_pair(&mut app, &factory, ["asset1", "asset2"]).unwrap();
+    create_pair(&mut app, &factory, ["asset3", "asset1"]).unwrap();
+
+    let first: PairsResponse = app.wrap().query_wasm_smart(factory.clone(), &QueryMsg::Pairs {
+        start_after: None,
+        limit: Some(2),
+    }).unwrap();
+    let first_pairs: Vec<_> = first.pairs.iter().map(|pair| pair.asset_infos.clone()).collect();
+    assert_eq!(first_pairs, vec![
+        ["asset1".to_string(), "asset2".to_string()],
+        ["asset1".to_string(), "asset3".to_string()],
+    ]);
+
+    let rest: PairsResponse = app.wrap().query_wasm_smart(factory.clone(), &QueryMsg::Pairs {
+        start_after: Some(["asset3".to_string(), "asset1".to_string()]),
+        limit: None,
+    }).unwrap();
+    assert_eq!(rest.pairs.len(), 1);
+    assert_eq!(rest.pairs[0].asset_infos, ["asset2".to_string(), "asset3".to_string()]);
+}
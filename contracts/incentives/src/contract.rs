@@ -0,0 +1,96 @@
+use amm_contract::msg::{Config as PoolConfig, QueryMsg as PoolQueryMsg};
+use cosmwasm_std::{Binary, Decimal, Decimal256, Deps, DepsMut, Env, MessageInfo, Response, StdResult, to_json_binary, Uint128};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cw2::set_contract_version;
+use crate::error::ContractError;
+use crate::execute::{claim, create_schedule, MAX_SCHEDULE_DENOMS, receive, set_schedule_denom, unbond, unlock, update_reward_rate};
+use crate::msg::{Config, ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::query::{query_active_schedules, query_config, query_locks, query_pending_rewards, query_staker};
+use crate::state::{CONFIG, REWARD_STATE, RewardState};
+
+const CONTRACT_NAME: &str = "amm-incentives";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError>  {
+    set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
+
+    // The bonded LP token is the one of the pool
+    let pool_addr = deps.api.addr_validate(&msg.pool_addr)?;
+    let pool_config: PoolConfig = deps.querier.query_wasm_smart(&pool_addr, &PoolQueryMsg::GetConfig {})?;
+    let lp_token_addr = deps.api.addr_validate(&pool_config.lp_token_addr)?;
+
+    // Locking boosts the rewards, and the penalty can take at most the whole lock
+    let invalid_lock_duration = msg.lock_durations.iter().enumerate().any(|(i, lock_duration)| {
+        lock_duration.duration == 0
+            || lock_duration.multiplier < Decimal::one()
+            || msg.lock_durations[..i].iter().any(|other| other.duration == lock_duration.duration)
+    });
+    if invalid_lock_duration || msg.early_unlock_penalty > Decimal::one() {
+        return Err(ContractError::InvalidLockConfig {});
+    }
+    if msg.schedule_denoms.iter().enumerate().any(|(i, schedule_denom)| {
+        msg.schedule_denoms[..i].iter().any(|other| other.denom == schedule_denom.denom)
+    }) {
+        return Err(ContractError::InvalidScheduleDenom {});
+    }
+    if msg.schedule_denoms.len() > MAX_SCHEDULE_DENOMS {
+        return Err(ContractError::TooManyScheduleDenoms {});
+    }
+
+    CONFIG.save(deps.storage, &Config {
+        owner: info.sender,
+        pool_addr,
+        lp_token_addr,
+        reward_denom: msg.reward_denom,
+        reward_rate: msg.reward_rate,
+        lock_durations: msg.lock_durations,
+        early_unlock_penalty: msg.early_unlock_penalty,
+        schedule_denoms: msg.schedule_denoms,
+    })?;
+    REWARD_STATE.save(deps.storage, &RewardState {
+        reward_per_token: Decimal256::zero(),
+        last_update: env.block.time.seconds(),
+        total_bonded: Uint128::zero(),
+        total_weight: Uint128::zero(),
+        total_lock_weight: Uint128::zero(),
+        penalty_per_weight: Decimal256::zero(),
+    })?;
+
+    Ok(Response::new().add_attribute("method", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError>  {
+    match msg {
+        ExecuteMsg::Receive(cw20_msg) => receive(deps, env, info, cw20_msg),
+        ExecuteMsg::Unbond { amount } => unbond(deps, env, info, amount),
+        ExecuteMsg::Unlock { lock_id } => unlock(deps, env, info, lock_id),
+        ExecuteMsg::Claim {} => claim(deps, env, info),
+        ExecuteMsg::UpdateRewardRate { reward_rate } => update_reward_rate(deps, env, info, reward_rate),
+        ExecuteMsg::CreateSchedule { start_time, end_time } => create_schedule(deps, env, info, start_time, end_time),
+        ExecuteMsg::SetScheduleDenom { denom, min_amount } => set_schedule_denom(deps, info, denom, min_amount),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::Staker { user } => to_json_binary(&query_staker(deps, user)?),
+        QueryMsg::Locks { user } => to_json_binary(&query_locks(deps, user)?),
+        QueryMsg::PendingRewards { user } => to_json_binary(&query_pending_rewards(deps, env, user)?),
+        QueryMsg::ActiveSchedules {} => to_json_binary(&query_active_schedules(deps, env)?),
+    }
+}
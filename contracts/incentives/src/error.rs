@@ -0,0 +1,53 @@
+use cosmwasm_std::{OverflowError, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Only the pool's LP token can be bonded")]
+    InvalidLpToken {},
+
+    #[error("Invalid zero amount")]
+    InvalidZeroAmount {},
+
+    #[error("Unbonding more than the bonded amount")]
+    InsufficientBond {},
+
+    #[error("No rewards to claim")]
+    NothingToClaim {},
+
+    #[error("Invalid funds sent")]
+    InvalidFunds {},
+
+    #[error("A schedule has to end after it starts, can't start in the past and can't start or run for more than a year")]
+    InvalidSchedule {},
+
+    #[error("Schedules are funded in one of the schedule denoms, which are distinct")]
+    InvalidScheduleDenom {},
+
+    #[error("Schedule funded with less than the minimum of its denom")]
+    InsufficientScheduleFunds {},
+
+    #[error("Too many active schedules")]
+    TooManyActiveSchedules {},
+
+    #[error("Too many schedule denoms")]
+    TooManyScheduleDenoms {},
+
+    #[error("Lock durations must be distinct and non-zero with multipliers of at least one, and the penalty at most one")]
+    InvalidLockConfig {},
+
+    #[error("Not one of the lock durations")]
+    InvalidLockDuration {},
+
+    #[error("Lock not found")]
+    LockNotFound {},
+}
@@ -0,0 +1,503 @@
+use cosmwasm_std::{Addr, BankMsg, coin, Coins, CosmosMsg, Decimal256, DepsMut, Env, from_json, MessageInfo, Order, Response, StdError, StdResult, Storage, to_json_binary, Uint128, Uint256, WasmMsg};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use crate::error::ContractError;
+use crate::msg::{Config, ReceiveMsg};
+use crate::state::{CONFIG, DENOM_STAKERS, DenomStakerInfo, ENDED_REWARD_PER_TOKEN, Lock, LOCKS, NEXT_LOCK_ID, NEXT_SCHEDULE_ID, REWARD_STATE, RewardSchedule, RewardState, SCHEDULE_STAKERS, ScheduleStakerInfo, SCHEDULES, StakerInfo, STAKERS};
+
+// Every staker update goes through the active schedules and the schedule denoms, so both are capped.
+// Schedules have a minimum funding and can't run for more than a year, so they can't hold the slots for long
+pub const MAX_ACTIVE_SCHEDULES: usize = 10;
+pub const MAX_SCHEDULE_DENOMS: usize = 5;
+pub const MAX_SCHEDULE_DURATION: u64 = 365 * 24 * 60 * 60;
+
+// rewards / total_weight as a reward per unit of weight
+fn reward_per_token(rewards: Uint256, total_weight: Uint256) -> StdResult<Decimal256> {
+    Decimal256::checked_from_ratio(rewards, total_weight).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+// Rewards earned by a weight since the reward per token was `reward_per_token_paid`
+fn earned(weight: Uint128, reward_per_token: Decimal256, reward_per_token_paid: Decimal256) -> StdResult<Uint128> {
+    let growth = reward_per_token.checked_sub(reward_per_token_paid)?;
+    Ok(Uint128::try_from(Uint256::from(weight).mul_floor(growth))?)
+}
+
+// Add the rewards paid out since the last update to the reward per token.
+// Nothing accrues while no LP tokens are bonded
+pub fn accrue_rewards(state: &mut RewardState, config: &Config, now: u64) -> StdResult<()> {
+    if now > state.last_update && !state.total_weight.is_zero() {
+        let elapsed = now - state.last_update;
+        let rewards = Uint256::from(config.reward_rate).checked_mul(Uint256::from(elapsed))?;
+        let accrued = reward_per_token(rewards, Uint256::from(state.total_weight))?;
+        state.reward_per_token = state.reward_per_token.checked_add(accrued)?;
+    }
+    state.last_update = now;
+    Ok(())
+}
+
+// Add the share of the schedule's rewards paid out since its last update, within its start and end time,
+// to its reward per token. While nothing is bonded the rest of the schedule is pushed back instead
+pub fn accrue_schedule(schedule: &mut RewardSchedule, total_weight: Uint128, now: u64) -> StdResult<()> {
+    let from = schedule.last_update.max(schedule.start_time);
+    let to = now.min(schedule.end_time);
+    if total_weight.is_zero() {
+        if from < schedule.end_time && now > from {
+            let idle = now - from;
+            schedule.start_time = schedule.start_time.saturating_add(idle);
+            schedule.end_time = schedule.end_time.saturating_add(idle);
+        }
+    } else if to > from {
+        let rewards = Uint256::from(schedule.amount).checked_mul(Uint256::from(to - from))?;
+        let duration = Uint256::from(schedule.end_time - schedule.start_time);
+        let accrued = reward_per_token(rewards, duration.checked_mul(Uint256::from(total_weight))?)?;
+        schedule.reward_per_token = schedule.reward_per_token.checked_add(accrued)?;
+    }
+    schedule.last_update = schedule.last_update.max(now);
+    Ok(())
+}
+
+// Move the rewards earned by the staker's weight and the penalties shared with their locks
+// since their last update to their pending rewards and penalties
+pub fn accrue_staker_rewards(staker: &mut StakerInfo, state: &RewardState) -> StdResult<()> {
+    let rewards = earned(staker.weight()?, state.reward_per_token, staker.reward_per_token_paid)?;
+    staker.pending_rewards = staker.pending_rewards.checked_add(rewards)?;
+    staker.reward_per_token_paid = state.reward_per_token;
+
+    let penalties = earned(staker.lock_weight, state.penalty_per_weight, staker.penalty_per_weight_paid)?;
+    staker.pending_penalties = staker.pending_penalties.checked_add(penalties)?;
+    staker.penalty_per_weight_paid = state.penalty_per_weight;
+    Ok(())
+}
+
+// Rewards of the reward rate and of the schedules, brought up to date for a staker. The staker's rewards
+// are tracked per schedule while it's active, and once it ends they move to the rewards of the ended schedules
+// of its denom. So the work is bounded by the active schedules and the schedule denoms, however many schedules
+// were ever created
+pub(crate) struct Accrued {
+    pub state: RewardState,
+    pub staker: StakerInfo,
+    /// Schedules that haven't ended, with the staker's rewards of each
+    pub schedules: Vec<(u64, RewardSchedule, ScheduleStakerInfo)>,
+    pub ended_schedules: Vec<u64>,
+    /// Ended schedules the staker's rewards were moved from
+    pub ended_schedule_stakers: Vec<u64>,
+    /// Each schedule denom with the reward per token of its ended schedules and the staker's rewards of them
+    pub denoms: Vec<(String, Decimal256, DenomStakerInfo)>,
+}
+
+fn find_denom<'a, T>(denoms: &'a mut [(String, T)], denom: &str) -> StdResult<&'a mut T> {
+    denoms
+        .iter_mut()
+        .find(|(schedule_denom, _)| schedule_denom == denom)
+        .map(|(_, value)| value)
+        .ok_or_else(|| StdError::generic_err("Not a schedule denom"))
+}
+
+pub(crate) fn load_accrued(storage: &dyn Storage, config: &Config, env: &Env, user: &Addr) -> StdResult<Accrued> {
+    let now = env.block.time.seconds();
+    let mut state = REWARD_STATE.load(storage)?;
+    accrue_rewards(&mut state, config, now)?;
+
+    let mut staker = STAKERS.may_load(storage, user)?.unwrap_or_default();
+    accrue_staker_rewards(&mut staker, &state)?;
+    let weight = staker.weight()?;
+
+    let mut ended_reward_per_token = config.schedule_denoms
+        .iter()
+        .map(|schedule_denom| {
+            let reward_per_token = ENDED_REWARD_PER_TOKEN.may_load(storage, &schedule_denom.denom)?.unwrap_or_default();
+            Ok((schedule_denom.denom.clone(), reward_per_token))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut schedules = vec![];
+    let mut ended_schedules = vec![];
+    for item in SCHEDULES.range(storage, None, None, Order::Ascending) {
+        let (id, mut schedule) = item?;
+        accrue_schedule(&mut schedule, state.total_weight, now)?;
+        if schedule.last_update >= schedule.end_time {
+            let ended = find_denom(&mut ended_reward_per_token, &schedule.denom)?;
+            *ended = ended.checked_add(schedule.reward_per_token)?;
+            ended_schedules.push(id);
+        } else {
+            let schedule_staker = SCHEDULE_STAKERS.may_load(storage, (user, id))?
+                .unwrap_or_else(|| ScheduleStakerInfo { denom: schedule.denom.clone(), ..Default::default() });
+            schedules.push((id, schedule, schedule_staker));
+        }
+    }
+
+    // The rewards of the ended schedules since the staker's last update are the growth of the reward per token
+    // of the denom's ended and active schedules, less the growth of the active ones, which the staker's rewards
+    // per schedule track. Schedules created since then count from zero
+    let mut denoms = ended_reward_per_token
+        .into_iter()
+        .map(|(denom, ended)| {
+            let mut denom_staker = DENOM_STAKERS.may_load(storage, (user, &denom))?.unwrap_or_default();
+            let mut reward_per_token = ended;
+            let mut active_paid = Decimal256::zero();
+            for (_, schedule, schedule_staker) in schedules.iter().filter(|(_, schedule, _)| schedule.denom == denom) {
+                reward_per_token = reward_per_token.checked_add(schedule.reward_per_token)?;
+                active_paid = active_paid.checked_add(schedule_staker.reward_per_token_paid)?;
+            }
+            let rewards = earned(weight, ended.checked_add(active_paid)?, denom_staker.reward_per_token_paid)?;
+            denom_staker.pending_rewards = denom_staker.pending_rewards.checked_add(rewards)?;
+            denom_staker.reward_per_token_paid = reward_per_token;
+            Ok((denom, (ended, denom_staker)))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for (_, schedule, schedule_staker) in schedules.iter_mut() {
+        let rewards = earned(weight, schedule.reward_per_token, schedule_staker.reward_per_token_paid)?;
+        schedule_staker.pending_rewards = schedule_staker.pending_rewards.checked_add(rewards)?;
+        schedule_staker.reward_per_token_paid = schedule.reward_per_token;
+    }
+
+    // The staker only has rewards of the schedules active at their last update, those that ended since
+    // move to the rewards of their denom
+    let mut ended_schedule_stakers = vec![];
+    for item in SCHEDULE_STAKERS.prefix(user).range(storage, None, None, Order::Ascending) {
+        let (id, schedule_staker) = item?;
+        if !schedules.iter().any(|(active_id, ..)| *active_id == id) {
+            let (_, denom_staker) = find_denom(&mut denoms, &schedule_staker.denom)?;
+            denom_staker.pending_rewards = denom_staker.pending_rewards.checked_add(schedule_staker.pending_rewards)?;
+            ended_schedule_stakers.push(id);
+        }
+    }
+
+    let denoms = denoms
+        .into_iter()
+        .map(|(denom, (ended, denom_staker))| (denom, ended, denom_staker))
+        .collect();
+    Ok(Accrued { state, staker, schedules, ended_schedules, ended_schedule_stakers, denoms })
+}
+
+fn save_accrued(storage: &mut dyn Storage, user: &Addr, accrued: &Accrued) -> StdResult<()> {
+    REWARD_STATE.save(storage, &accrued.state)?;
+    STAKERS.save(storage, user, &accrued.staker)?;
+    for (id, schedule, schedule_staker) in &accrued.schedules {
+        SCHEDULES.save(storage, *id, schedule)?;
+        SCHEDULE_STAKERS.save(storage, (user, *id), schedule_staker)?;
+    }
+    for id in &accrued.ended_schedules {
+        SCHEDULES.remove(storage, *id);
+    }
+    for id in &accrued.ended_schedule_stakers {
+        SCHEDULE_STAKERS.remove(storage, (user, *id));
+    }
+    for (denom, ended, denom_staker) in &accrued.denoms {
+        ENDED_REWARD_PER_TOKEN.save(storage, denom, ended)?;
+        DENOM_STAKERS.save(storage, (user, denom), denom_staker)?;
+    }
+    Ok(())
+}
+
+fn transfer_lp_tokens(config: &Config, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: config.lp_token_addr.to_string(),
+        msg: to_json_binary(&Cw20ExecuteMsg::Transfer { recipient: recipient.to_string(), amount })?,
+        funds: vec![],
+    }
+        .into())
+}
+
+pub fn receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Only the LP token of the pool can be bonded
+    if info.sender != config.lp_token_addr {
+        return Err(ContractError::InvalidLpToken {});
+    }
+
+    let user = deps.api.addr_validate(&cw20_msg.sender)?;
+    match from_json(&cw20_msg.msg)? {
+        ReceiveMsg::Bond {} => bond(deps, env, config, user, cw20_msg.amount),
+        ReceiveMsg::Lock { duration } => lock(deps, env, config, user, cw20_msg.amount, duration),
+    }
+}
+
+fn bond(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    user: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut accrued = load_accrued(deps.storage, &config, &env, &user)?;
+    accrued.staker.bonded = accrued.staker.bonded.checked_add(amount)?;
+    accrued.state.total_bonded = accrued.state.total_bonded.checked_add(amount)?;
+    accrued.state.total_weight = accrued.state.total_weight.checked_add(amount)?;
+    save_accrued(deps.storage, &user, &accrued)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "bond")
+        .add_attribute("user", user)
+        .add_attribute("amount", amount))
+}
+
+fn lock(
+    deps: DepsMut,
+    env: Env,
+    config: Config,
+    user: Addr,
+    amount: Uint128,
+    duration: u64,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let multiplier = config.lock_durations
+        .iter()
+        .find(|lock_duration| lock_duration.duration == duration)
+        .ok_or(ContractError::InvalidLockDuration {})?
+        .multiplier;
+    let weight = amount.mul_floor(multiplier);
+
+    let mut accrued = load_accrued(deps.storage, &config, &env, &user)?;
+    accrued.staker.locked = accrued.staker.locked.checked_add(amount)?;
+    accrued.staker.lock_weight = accrued.staker.lock_weight.checked_add(weight)?;
+    accrued.state.total_bonded = accrued.state.total_bonded.checked_add(amount)?;
+    accrued.state.total_weight = accrued.state.total_weight.checked_add(weight)?;
+    accrued.state.total_lock_weight = accrued.state.total_lock_weight.checked_add(weight)?;
+    save_accrued(deps.storage, &user, &accrued)?;
+
+    let lock_id = NEXT_LOCK_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_LOCK_ID.save(deps.storage, &lock_id.checked_add(1).ok_or_else(|| StdError::generic_err("Lock id overflow"))?)?;
+    let unlock_time = env.block.time.seconds().checked_add(duration).ok_or_else(|| StdError::generic_err("Unlock time overflow"))?;
+    LOCKS.save(deps.storage, (&user, lock_id), &Lock { amount, multiplier, weight, unlock_time })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "lock")
+        .add_attribute("user", user)
+        .add_attribute("lock_id", lock_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("unlock_time", unlock_time.to_string()))
+}
+
+pub fn unlock(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    lock_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let lock = LOCKS.may_load(deps.storage, (&info.sender, lock_id))?.ok_or(ContractError::LockNotFound {})?;
+    LOCKS.remove(deps.storage, (&info.sender, lock_id));
+
+    let mut accrued = load_accrued(deps.storage, &config, &env, &info.sender)?;
+    accrued.staker.locked = accrued.staker.locked.checked_sub(lock.amount)?;
+    accrued.staker.lock_weight = accrued.staker.lock_weight.checked_sub(lock.weight)?;
+    accrued.state.total_bonded = accrued.state.total_bonded.checked_sub(lock.amount)?;
+    accrued.state.total_weight = accrued.state.total_weight.checked_sub(lock.weight)?;
+    accrued.state.total_lock_weight = accrued.state.total_lock_weight.checked_sub(lock.weight)?;
+
+    // Unlocking early leaves the penalty to the remaining lockers, if there are any
+    let mut penalty = Uint128::zero();
+    if env.block.time.seconds() < lock.unlock_time && !accrued.state.total_lock_weight.is_zero() {
+        penalty = lock.amount.mul_floor(config.early_unlock_penalty);
+        let penalty_per_weight = reward_per_token(Uint256::from(penalty), Uint256::from(accrued.state.total_lock_weight))?;
+        accrued.state.penalty_per_weight = accrued.state.penalty_per_weight.checked_add(penalty_per_weight)?;
+    }
+    save_accrued(deps.storage, &info.sender, &accrued)?;
+
+    let amount = lock.amount.checked_sub(penalty)?;
+    let mut response = Response::new();
+    if !amount.is_zero() {
+        response = response.add_message(transfer_lp_tokens(&config, &info.sender, amount)?);
+    }
+
+    Ok(response
+        .add_attribute("action", "unlock")
+        .add_attribute("user", info.sender)
+        .add_attribute("lock_id", lock_id.to_string())
+        .add_attribute("amount", amount)
+        .add_attribute("penalty", penalty))
+}
+
+pub fn unbond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut accrued = load_accrued(deps.storage, &config, &env, &info.sender)?;
+    if amount > accrued.staker.bonded {
+        return Err(ContractError::InsufficientBond {});
+    }
+    accrued.staker.bonded = accrued.staker.bonded.checked_sub(amount)?;
+    accrued.state.total_bonded = accrued.state.total_bonded.checked_sub(amount)?;
+    accrued.state.total_weight = accrued.state.total_weight.checked_sub(amount)?;
+    save_accrued(deps.storage, &info.sender, &accrued)?;
+
+    // Return the LP tokens to the staker
+    Ok(Response::new()
+        .add_message(transfer_lp_tokens(&config, &info.sender, amount)?)
+        .add_attribute("action", "unbond")
+        .add_attribute("user", info.sender)
+        .add_attribute("amount", amount))
+}
+
+pub fn claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut accrued = load_accrued(deps.storage, &config, &env, &info.sender)?;
+
+    // The rewards of the reward rate and of the schedules, added up per denom
+    let mut rewards = Coins::default();
+    rewards.add(coin(accrued.staker.pending_rewards.u128(), &config.reward_denom))?;
+    accrued.staker.pending_rewards = Uint128::zero();
+    for (_, schedule, schedule_staker) in accrued.schedules.iter_mut() {
+        rewards.add(coin(schedule_staker.pending_rewards.u128(), &schedule.denom))?;
+        schedule_staker.pending_rewards = Uint128::zero();
+    }
+    for (denom, _, denom_staker) in accrued.denoms.iter_mut() {
+        rewards.add(coin(denom_staker.pending_rewards.u128(), denom.as_str()))?;
+        denom_staker.pending_rewards = Uint128::zero();
+    }
+    let penalties = accrued.staker.pending_penalties;
+    accrued.staker.pending_penalties = Uint128::zero();
+    if rewards.is_empty() && penalties.is_zero() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    save_accrued(deps.storage, &info.sender, &accrued)?;
+
+    // The rewards are paid from the contract's balance
+    let rewards_attribute = rewards.to_string();
+    let mut response = Response::new();
+    if !rewards.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: rewards.into_vec(),
+        });
+    }
+    if !penalties.is_zero() {
+        response = response.add_message(transfer_lp_tokens(&config, &info.sender, penalties)?);
+    }
+
+    Ok(response
+        .add_attribute("action", "claim")
+        .add_attribute("user", info.sender)
+        .add_attribute("rewards", rewards_attribute)
+        .add_attribute("lp_tokens", penalties))
+}
+
+pub fn update_reward_rate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    reward_rate: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Rewards up to now accrue at the old rate
+    let mut state = REWARD_STATE.load(deps.storage)?;
+    accrue_rewards(&mut state, &config, env.block.time.seconds())?;
+    REWARD_STATE.save(deps.storage, &state)?;
+
+    config.reward_rate = reward_rate;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_reward_rate")
+        .add_attribute("reward_rate", reward_rate))
+}
+
+pub fn create_schedule(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    start_time: u64,
+    end_time: u64,
+) -> Result<Response, ContractError> {
+    // Starts within the max duration and runs for at most the max duration, so pushing it back can't overflow
+    let now = env.block.time.seconds();
+    if start_time < now
+        || end_time <= start_time
+        || start_time - now > MAX_SCHEDULE_DURATION
+        || end_time - start_time > MAX_SCHEDULE_DURATION {
+        return Err(ContractError::InvalidSchedule {});
+    }
+
+    // The schedule is funded with a single native coin of one of the schedule denoms, at least its minimum
+    let funds = match info.funds.as_slice() {
+        [funds] if !funds.amount.is_zero() => funds.clone(),
+        _ => return Err(ContractError::InvalidFunds {}),
+    };
+    let config = CONFIG.load(deps.storage)?;
+    let schedule_denom = config.schedule_denoms
+        .iter()
+        .find(|schedule_denom| schedule_denom.denom == funds.denom)
+        .ok_or(ContractError::InvalidScheduleDenom {})?;
+    if funds.amount < schedule_denom.amount {
+        return Err(ContractError::InsufficientScheduleFunds {});
+    }
+
+    // Bring the schedules up to date, which removes the ended ones, before counting the active ones
+    let accrued = load_accrued(deps.storage, &config, &env, &info.sender)?;
+    save_accrued(deps.storage, &info.sender, &accrued)?;
+    if SCHEDULES.keys(deps.storage, None, None, Order::Ascending).count() >= MAX_ACTIVE_SCHEDULES {
+        return Err(ContractError::TooManyActiveSchedules {});
+    }
+
+    let id = NEXT_SCHEDULE_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_SCHEDULE_ID.save(deps.storage, &id.checked_add(1).ok_or_else(|| StdError::generic_err("Schedule id overflow"))?)?;
+
+    SCHEDULES.save(deps.storage, id, &RewardSchedule {
+        creator: info.sender.clone(),
+        denom: funds.denom.clone(),
+        amount: funds.amount,
+        start_time,
+        end_time,
+        reward_per_token: Decimal256::zero(),
+        last_update: now,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_schedule")
+        .add_attribute("schedule_id", id.to_string())
+        .add_attribute("creator", info.sender)
+        .add_attribute("rewards", funds.to_string()))
+}
+
+pub fn set_schedule_denom(
+    deps: DepsMut,
+    info: MessageInfo,
+    denom: String,
+    min_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let denom_count = config.schedule_denoms.len();
+    match config.schedule_denoms.iter_mut().find(|schedule_denom| schedule_denom.denom == denom) {
+        Some(schedule_denom) => schedule_denom.amount = min_amount,
+        None if denom_count >= MAX_SCHEDULE_DENOMS => return Err(ContractError::TooManyScheduleDenoms {}),
+        None => config.schedule_denoms.push(coin(min_amount.u128(), &denom)),
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_schedule_denom")
+        .add_attribute("denom", denom)
+        .add_attribute("min_amount", min_amount))
+}
@@ -0,0 +1,132 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// AMM pool whose LP token is bonded
+    pub pool_addr: String,
+    /// Native denom the rewards are paid in
+    pub reward_denom: String,
+    /// Rewards paid out per second, shared among the bonded LP tokens
+    pub reward_rate: Uint128,
+    /// Durations LP tokens can be locked for and their reward multipliers
+    pub lock_durations: Vec<LockDuration>,
+    /// Share of the locked LP tokens kept when unlocking early, which goes to the other lockers
+    pub early_unlock_penalty: Decimal,
+    /// Native denoms reward schedules can be funded in, with the minimum funding of a schedule
+    pub schedule_denoms: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct LockDuration {
+    /// Lock duration in seconds
+    pub duration: u64,
+    /// Reward weight of each locked LP token, at least one
+    pub multiplier: Decimal,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Bond LP tokens sent with the cw20 `Send` hook
+    Receive(Cw20ReceiveMsg),
+    /// Return bonded LP tokens to the sender, their rewards so far stay claimable
+    Unbond { amount: Uint128 },
+    /// Return the LP tokens of a lock to the sender. Before the lock ends, the early unlock
+    /// penalty is kept and shared among the other lockers
+    Unlock { lock_id: u64 },
+    /// Send the sender's pending rewards, and the LP tokens from early unlock penalties
+    Claim {},
+    /// Change the per-second reward rate, owner only
+    UpdateRewardRate { reward_rate: Uint128 },
+    /// Add a reward schedule funded with the single native coin of a schedule denom sent along,
+    /// paid out evenly to the bonded LP tokens between the start and end time (in seconds)
+    CreateSchedule { start_time: u64, end_time: u64 },
+    /// Allow reward schedules in a native denom or change its minimum funding, owner only
+    SetScheduleDenom { denom: String, min_amount: Uint128 },
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    Bond {},
+    /// Lock the LP tokens for one of the lock durations, for boosted rewards
+    Lock { duration: u64 },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    GetConfig {},
+    /// LP tokens bonded and locked by a user and their reward weight
+    Staker { user: Addr },
+    /// A user's locks
+    Locks { user: Addr },
+    /// Rewards a user can claim, from the reward rate, from each active schedule and from the ended schedules
+    PendingRewards { user: Addr },
+    /// Reward schedules that haven't ended yet
+    ActiveSchedules {},
+}
+
+#[cw_serde]
+pub struct Config {
+    pub owner: Addr,
+    pub pool_addr: Addr,
+    pub lp_token_addr: Addr,
+    pub reward_denom: String,
+    pub reward_rate: Uint128,
+    pub lock_durations: Vec<LockDuration>,
+    pub early_unlock_penalty: Decimal,
+    pub schedule_denoms: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct StakerResponse {
+    pub user: Addr,
+    pub bonded: Uint128,
+    pub locked: Uint128,
+    /// Bonded LP tokens plus the locked LP tokens times their multipliers
+    pub weight: Uint128,
+}
+
+#[cw_serde]
+pub struct LockResponse {
+    pub lock_id: u64,
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    pub unlock_time: u64,
+}
+
+#[cw_serde]
+pub struct LocksResponse {
+    pub locks: Vec<LockResponse>,
+}
+
+#[cw_serde]
+pub struct PendingRewardsResponse {
+    pub user: Addr,
+    pub rewards: Coin,
+    pub schedules: Vec<SchedulePendingRewards>,
+    /// Rewards of the ended schedules, per schedule denom
+    pub ended_schedule_rewards: Vec<Coin>,
+    /// LP tokens from the early unlock penalties of other lockers
+    pub lp_tokens: Uint128,
+}
+
+#[cw_serde]
+pub struct SchedulePendingRewards {
+    pub schedule_id: u64,
+    pub rewards: Coin,
+}
+
+#[cw_serde]
+pub struct ScheduleResponse {
+    pub schedule_id: u64,
+    pub creator: Addr,
+    pub rewards: Coin,
+    pub start_time: u64,
+    pub end_time: u64,
+}
+
+#[cw_serde]
+pub struct ActiveSchedulesResponse {
+    pub schedules: Vec<ScheduleResponse>,
+}
@@ -0,0 +1,81 @@
+use cosmwasm_std::{Addr, coin, Deps, Env, Order, StdResult};
+use crate::execute::load_accrued;
+use crate::msg::{ActiveSchedulesResponse, Config, LockResponse, LocksResponse, PendingRewardsResponse, SchedulePendingRewards, ScheduleResponse, StakerResponse};
+use crate::state::{CONFIG, LOCKS, SCHEDULES, STAKERS};
+
+pub fn query_config(deps: Deps) -> StdResult<Config> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(config)
+}
+
+pub fn query_staker(deps: Deps, user: Addr) -> StdResult<StakerResponse> {
+    let staker = STAKERS.may_load(deps.storage, &user)?.unwrap_or_default();
+    Ok(StakerResponse {
+        user,
+        bonded: staker.bonded,
+        locked: staker.locked,
+        weight: staker.weight()?,
+    })
+}
+
+pub fn query_locks(deps: Deps, user: Addr) -> StdResult<LocksResponse> {
+    let locks = LOCKS
+        .prefix(&user)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (lock_id, lock) = item?;
+            Ok(LockResponse {
+                lock_id,
+                amount: lock.amount,
+                multiplier: lock.multiplier,
+                unlock_time: lock.unlock_time,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(LocksResponse { locks })
+}
+
+pub fn query_pending_rewards(deps: Deps, env: Env, user: Addr) -> StdResult<PendingRewardsResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let accrued = load_accrued(deps.storage, &config, &env, &user)?;
+    let schedules = accrued.schedules
+        .into_iter()
+        .map(|(schedule_id, schedule, schedule_staker)| SchedulePendingRewards {
+            schedule_id,
+            rewards: coin(schedule_staker.pending_rewards.u128(), schedule.denom),
+        })
+        .collect();
+    let ended_schedule_rewards = accrued.denoms
+        .into_iter()
+        .map(|(denom, _, denom_staker)| coin(denom_staker.pending_rewards.u128(), denom))
+        .collect();
+
+    Ok(PendingRewardsResponse {
+        user,
+        rewards: coin(accrued.staker.pending_rewards.u128(), config.reward_denom),
+        schedules,
+        ended_schedule_rewards,
+        lp_tokens: accrued.staker.pending_penalties,
+    })
+}
+
+pub fn query_active_schedules(deps: Deps, env: Env) -> StdResult<ActiveSchedulesResponse> {
+    let now = env.block.time.seconds();
+    let schedules = SCHEDULES
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| item.as_ref().map_or(true, |(_, schedule)| schedule.end_time > now))
+        .map(|item| {
+            let (schedule_id, schedule) = item?;
+            Ok(ScheduleResponse {
+                schedule_id,
+                creator: schedule.creator,
+                rewards: coin(schedule.amount.u128(), schedule.denom),
+                start_time: schedule.start_time,
+                end_time: schedule.end_time,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ActiveSchedulesResponse { schedules })
+}
@@ -0,0 +1,104 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Decimal, Decimal256, StdResult, Uint128};
+use cw_storage_plus::{Item, Map};
+use crate::msg::Config;
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+// Rewards accrued per unit of reward weight since instantiation, updated before every change
+// of the bonded amounts or the reward rate. Bonded LP tokens weigh one, locked ones their multiplier
+#[cw_serde]
+pub struct RewardState {
+    pub reward_per_token: Decimal256,
+    pub last_update: u64,
+    /// LP tokens bonded and locked
+    pub total_bonded: Uint128,
+    pub total_weight: Uint128,
+    /// Weight of the locked LP tokens, which share the early unlock penalties
+    pub total_lock_weight: Uint128,
+    /// LP tokens of early unlock penalties per unit of lock weight
+    pub penalty_per_weight: Decimal256,
+}
+
+pub const REWARD_STATE: Item<RewardState> = Item::new("reward_state");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct StakerInfo {
+    pub bonded: Uint128,
+    pub locked: Uint128,
+    pub lock_weight: Uint128,
+    /// Reward per token already accounted for in `pending_rewards`
+    pub reward_per_token_paid: Decimal256,
+    pub pending_rewards: Uint128,
+    /// Penalty per weight already accounted for in `pending_penalties`
+    pub penalty_per_weight_paid: Decimal256,
+    pub pending_penalties: Uint128,
+}
+
+impl StakerInfo {
+    pub fn weight(&self) -> StdResult<Uint128> {
+        Ok(self.bonded.checked_add(self.lock_weight)?)
+    }
+}
+
+pub const STAKERS: Map<&Addr, StakerInfo> = Map::new("stakers");
+
+#[cw_serde]
+pub struct Lock {
+    pub amount: Uint128,
+    pub multiplier: Decimal,
+    /// Reward weight of the lock, the amount times the multiplier
+    pub weight: Uint128,
+    pub unlock_time: u64,
+}
+
+// Locks keyed by owner and lock id
+pub const LOCKS: Map<(&Addr, u64), Lock> = Map::new("locks");
+
+pub const NEXT_LOCK_ID: Item<u64> = Item::new("next_lock_id");
+
+// Rewards funded by anyone and paid out evenly between the start and end time. Both are pushed back by
+// the time nothing is bonded, so none of the rewards go unpaid, and the schedule is removed once it ends
+#[cw_serde]
+pub struct RewardSchedule {
+    pub creator: Addr,
+    pub denom: String,
+    pub amount: Uint128,
+    pub start_time: u64,
+    pub end_time: u64,
+    pub reward_per_token: Decimal256,
+    pub last_update: u64,
+}
+
+// Schedules that haven't ended yet
+pub const SCHEDULES: Map<u64, RewardSchedule> = Map::new("schedules");
+
+pub const NEXT_SCHEDULE_ID: Item<u64> = Item::new("next_schedule_id");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct ScheduleStakerInfo {
+    pub denom: String,
+    /// Reward per token of the schedule already accounted for in `pending_rewards`
+    pub reward_per_token_paid: Decimal256,
+    pub pending_rewards: Uint128,
+}
+
+// A staker's rewards per schedule that hasn't ended, keyed by staker and schedule id
+pub const SCHEDULE_STAKERS: Map<(&Addr, u64), ScheduleStakerInfo> = Map::new("schedule_stakers");
+
+// Final reward per token of the ended schedules of each denom, added up
+pub const ENDED_REWARD_PER_TOKEN: Map<&str, Decimal256> = Map::new("ended_reward_per_token");
+
+#[cw_serde]
+#[derive(Default)]
+pub struct DenomStakerInfo {
+    /// Reward per token of the denom's ended and active schedules at the staker's last update
+    pub reward_per_token_paid: Decimal256,
+    /// Rewards of the denom's ended schedules
+    pub pending_rewards: Uint128,
+}
+
+// A staker's rewards of ended schedules per denom, keyed by staker and denom
+pub const DENOM_STAKERS: Map<(&Addr, &str), DenomStakerInfo> = Map::new("denom_stakers");
@@ -0,0 +1,435 @@
+use cosmwasm_std::{Addr, Binary, coin, Decimal, to_json_binary, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use cw_multi_test::error::AnyResult;
+use amm_contract::msg::{Config as PoolConfig, InstantiateMsg as PoolInstantiateMsg, PoolType};
+use amm_incentives::msg::{ActiveSchedulesResponse, ExecuteMsg, InstantiateMsg, LockDuration, LocksResponse, PendingRewardsResponse, QueryMsg, ReceiveMsg, SchedulePendingRewards, StakerResponse};
+use amm_incentives::error::ContractError;
+use amm_incentives::execute::{MAX_ACTIVE_SCHEDULES, MAX_SCHEDULE_DENOMS, MAX_SCHEDULE_DURATION};
+
+// cw20 token with balances of 80 for alice and 20 for bob
+fn instantiate_token(app: &mut App, label: &str) -> Addr {
+    let code = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &cw20_base::msg::InstantiateMsg {
+            name: "AMM LP token".to_string(),
+            symbol: "AMMLP".to_string(),
+            decimals: 6,
+            initial_balances: vec![
+                Cw20Coin { address: "alice".to_string(), amount: Uint128::new(80) },
+                Cw20Coin { address: "bob".to_string(), amount: Uint128::new(20) },
+            ],
+            mint: Some(MinterResponse { minter: "owner".to_string(), cap: None }),
+            marketing: None,
+        },
+        &[],
+        label,
+        None,
+    ).unwrap()
+}
+
+const MONTH: u64 = 30 * 24 * 60 * 60;
+
+// Pool with its LP token and an incentives contract paying 100 gov per second, funded with 1000000 gov.
+// Locks for 1, 3 and 12 months weigh 1.5, 2 and 4 times, unlocking early costs half the lock
+fn setup_incentives(app: &mut App) -> (Addr, Addr) {
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "owner".to_string(),
+        amount: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_token(app, "mock-lp-token");
+
+    let pool_code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let pool_code_id = app.store_code(Box::new(pool_code));
+    let pool = app.instantiate_contract(
+        pool_code_id,
+        Addr::unchecked("owner"),
+        &PoolInstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: PoolConfig {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let incentives_code = ContractWrapper::new(
+        amm_incentives::contract::execute,
+        amm_incentives::contract::instantiate,
+        amm_incentives::contract::query,
+    );
+    let incentives_code_id = app.store_code(Box::new(incentives_code));
+    let incentives = app.instantiate_contract(
+        incentives_code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            pool_addr: pool.to_string(),
+            reward_denom: "gov".to_string(),
+            reward_rate: Uint128::new(100),
+            lock_durations: vec![
+                LockDuration { duration: MONTH, multiplier: Decimal::percent(150) },
+                LockDuration { duration: 3 * MONTH, multiplier: Decimal::percent(200) },
+                LockDuration { duration: 12 * MONTH, multiplier: Decimal::percent(400) },
+            ],
+            early_unlock_penalty: Decimal::percent(50),
+            schedule_denoms: vec![coin(100,"partner"), coin(100,"gov")],
+        },
+        &[],
+        "mock-amm-incentives",
+        None,
+    ).unwrap();
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: incentives.to_string(),
+        amount: vec![coin(1000000,"gov")],
+    }))
+        .unwrap();
+
+    (incentives, lp_token)
+}
+
+fn bond(app: &mut App, token: &Addr, incentives: &Addr, user: &str, amount: u128) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked(user),
+        token.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: incentives.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&ReceiveMsg::Bond {}).unwrap(),
+        },
+        &[],
+    )
+}
+
+fn lock(app: &mut App, token: &Addr, incentives: &Addr, user: &str, amount: u128, duration: u64) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        Addr::unchecked(user),
+        token.clone(),
+        &Cw20ExecuteMsg::Send {
+            contract: incentives.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(&ReceiveMsg::Lock { duration }).unwrap(),
+        },
+        &[],
+    )
+}
+
+fn lp_balance(app: &App, lp_token: &Addr, user: &str) -> Uint128 {
+    let balance: BalanceResponse = app.wrap().query_wasm_smart(lp_token, &Cw20QueryMsg::Balance { address: user.to_string() }).unwrap();
+    balance.balance
+}
+
+fn pending_rewards(app: &App, incentives: &Addr, user: &str) -> Uint128 {
+    let response: PendingRewardsResponse = app.wrap().query_wasm_smart(incentives, &QueryMsg::PendingRewards { user: Addr::unchecked(user) }).unwrap();
+    assert_eq!(response.rewards.denom, "gov");
+    response.rewards.amount
+}
+
+fn advance_seconds(app: &mut App, seconds: u64) {
+    app.update_block(|block| block.time = block.time.plus_seconds(seconds));
+}
+
+#[test]
+fn rewards_accrue_per_bonded_token() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+    advance_seconds(&mut app, 10);
+
+    // Alice holds the whole bond for 10 seconds
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(1000));
+
+    bond(&mut app, &lp_token, &incentives, "bob", 20).unwrap();
+    advance_seconds(&mut app, 10);
+
+    // The next 1000 are shared 80/20
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(1800));
+    assert_eq!(pending_rewards(&app, &incentives, "bob"), Uint128::new(200));
+
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance("alice", "gov").unwrap().amount, Uint128::new(1800));
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::zero());
+
+    // Bob gets the LP tokens back and keeps the rewards earned so far
+    app.execute_contract(Addr::unchecked("bob"), incentives.clone(), &ExecuteMsg::Unbond { amount: Uint128::new(20) }, &[]).unwrap();
+    assert_eq!(lp_balance(&app, &lp_token, "bob"), Uint128::new(20));
+    let staker: StakerResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::Staker { user: Addr::unchecked("bob") }).unwrap();
+    assert_eq!(staker.bonded, Uint128::zero());
+
+    advance_seconds(&mut app, 10);
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(1000));
+    assert_eq!(pending_rewards(&app, &incentives, "bob"), Uint128::new(200));
+
+    // Nothing accrues after the rate is set to zero
+    app.execute_contract(Addr::unchecked("owner"), incentives.clone(), &ExecuteMsg::UpdateRewardRate { reward_rate: Uint128::zero() }, &[]).unwrap();
+    advance_seconds(&mut app, 10);
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(1000));
+
+    app.execute_contract(Addr::unchecked("bob"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance("bob", "gov").unwrap().amount, Uint128::new(200));
+}
+
+#[test]
+fn bonding_errors() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+
+    // Only the pool's LP token can be bonded
+    let other_token = instantiate_token(&mut app, "mock-other-token");
+    bond(&mut app, &other_token, &incentives, "alice", 80).unwrap_err();
+
+    // Bonding needs the hook message
+    app.execute_contract(
+        Addr::unchecked("alice"),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::Send { contract: incentives.to_string(), amount: Uint128::new(80), msg: Binary::default() },
+        &[],
+    ).unwrap_err();
+
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+
+    // Unbonding more than the bond
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Unbond { amount: Uint128::new(81) }, &[]).unwrap_err();
+
+    // No time passed, so there is nothing to claim
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap_err();
+
+    // Only the owner sets the reward rate
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::UpdateRewardRate { reward_rate: Uint128::new(1000) }, &[]).unwrap_err();
+}
+
+#[test]
+fn reward_schedules_pay_out_alongside_the_reward_rate() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+    let start = app.block_info().time.seconds();
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "partner".to_string(),
+        amount: vec![coin(1000,"partner"), coin(400,"gov")],
+    }))
+        .unwrap();
+
+    // 100 partner per second between 10 and 20 seconds from now, 10 gov per second for 40 seconds
+    let partner = Addr::unchecked("partner");
+    app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: start + 10, end_time: start + 20 }, &[coin(1000,"partner")]).unwrap();
+    app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: start, end_time: start + 40 }, &[coin(400,"gov")]).unwrap();
+
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+    bond(&mut app, &lp_token, &incentives, "bob", 20).unwrap();
+    advance_seconds(&mut app, 20);
+
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("bob") }).unwrap();
+    assert_eq!(pending.rewards, coin(400,"gov"));
+    assert_eq!(pending.schedules, vec![SchedulePendingRewards { schedule_id: 1, rewards: coin(40,"gov") }]);
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(200,"partner"), coin(0,"gov")]);
+
+    // The first schedule has ended
+    let active: ActiveSchedulesResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::ActiveSchedules {}).unwrap();
+    assert_eq!(active.schedules.len(), 1);
+    assert_eq!(active.schedules[0].schedule_id, 1);
+    assert_eq!(active.schedules[0].rewards, coin(400,"gov"));
+
+    // Claim pays out the schedules, added up per denom
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance("alice", "gov").unwrap().amount, Uint128::new(1600 + 160));
+    assert_eq!(app.wrap().query_balance("alice", "partner").unwrap().amount, Uint128::new(800));
+
+    advance_seconds(&mut app, 30);
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("alice") }).unwrap();
+    assert_eq!(pending.rewards, coin(2400,"gov"));
+    assert!(pending.schedules.is_empty());
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(0,"partner"), coin(160,"gov")]);
+    let active: ActiveSchedulesResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::ActiveSchedules {}).unwrap();
+    assert!(active.schedules.is_empty());
+
+    // Schedules start now or later, end after they start, last at most the max duration and are funded with
+    // at least the minimum of one of the schedule denoms
+    let now = app.block_info().time.seconds();
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "partner".to_string(),
+        amount: vec![coin(1000,"partner"), coin(1000,"gov"), coin(100,"other")],
+    }))
+        .unwrap();
+    for (start_time, end_time, funds) in [
+        (now - 1, now + 10, vec![coin(100,"partner")]),
+        (now + 10, now + 10, vec![coin(100,"partner")]),
+        (now, now + 10, vec![]),
+        (now, now + 10, vec![coin(100,"gov"), coin(100,"partner")]),
+        (now, now + 10, vec![coin(100,"other")]),
+        (now, now + 10, vec![coin(99,"partner")]),
+        (now, now + MAX_SCHEDULE_DURATION + 1, vec![coin(100,"partner")]),
+        (now + MAX_SCHEDULE_DURATION + 1, now + MAX_SCHEDULE_DURATION + 10, vec![coin(100,"partner")]),
+        (now, u64::MAX, vec![coin(100,"partner")]),
+    ] {
+        app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time, end_time }, &funds).unwrap_err();
+    }
+}
+
+#[test]
+fn locks_boost_rewards_and_share_early_unlock_penalties() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+
+    // Weights of 80 * 1.5 = 120 and 20 * 4 = 80
+    lock(&mut app, &lp_token, &incentives, "alice", 80, MONTH).unwrap();
+    lock(&mut app, &lp_token, &incentives, "bob", 20, 12 * MONTH).unwrap();
+    let staker: StakerResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::Staker { user: Addr::unchecked("alice") }).unwrap();
+    assert_eq!((staker.bonded, staker.locked, staker.weight), (Uint128::zero(), Uint128::new(80), Uint128::new(120)));
+
+    advance_seconds(&mut app, 10);
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(600));
+    assert_eq!(pending_rewards(&app, &incentives, "bob"), Uint128::new(400));
+
+    // Alice unlocks early and leaves half of the lock to Bob, the only other locker
+    let locks: LocksResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::Locks { user: Addr::unchecked("alice") }).unwrap();
+    assert_eq!(locks.locks[0].unlock_time, app.block_info().time.seconds() - 10 + MONTH);
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Unlock { lock_id: locks.locks[0].lock_id }, &[]).unwrap();
+    assert_eq!(lp_balance(&app, &lp_token, "alice"), Uint128::new(40));
+    let locks: LocksResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::Locks { user: Addr::unchecked("alice") }).unwrap();
+    assert!(locks.locks.is_empty());
+
+    advance_seconds(&mut app, 10);
+    assert_eq!(pending_rewards(&app, &incentives, "alice"), Uint128::new(600));
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("bob") }).unwrap();
+    assert_eq!(pending.rewards, coin(1400,"gov"));
+    assert_eq!(pending.lp_tokens, Uint128::new(40));
+
+    app.execute_contract(Addr::unchecked("bob"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance("bob", "gov").unwrap().amount, Uint128::new(1400));
+    assert_eq!(lp_balance(&app, &lp_token, "bob"), Uint128::new(40));
+
+    // Locks can only be unlocked by their owner, and unlock without a penalty once they end
+    let locks: LocksResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::Locks { user: Addr::unchecked("bob") }).unwrap();
+    let lock_id = locks.locks[0].lock_id;
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Unlock { lock_id }, &[]).unwrap_err();
+    advance_seconds(&mut app, 12 * MONTH);
+    app.execute_contract(Addr::unchecked("bob"), incentives.clone(), &ExecuteMsg::Unlock { lock_id }, &[]).unwrap();
+    assert_eq!(lp_balance(&app, &lp_token, "bob"), Uint128::new(60));
+
+    // Only the configured durations can be locked for
+    lock(&mut app, &lp_token, &incentives, "bob", 20, 2 * MONTH).unwrap_err();
+}
+
+#[test]
+fn reward_schedules_are_bounded() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "partner".to_string(),
+        amount: vec![coin(2000,"partner"), coin(1000,"gov")],
+    }))
+        .unwrap();
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+
+    // Schedules of the minimum funding fill up the active schedules, but not for longer than they last
+    let partner = Addr::unchecked("partner");
+    let now = app.block_info().time.seconds();
+    for _ in 0..MAX_ACTIVE_SCHEDULES {
+        app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: now, end_time: now + 10 }, &[coin(100,"partner")]).unwrap();
+    }
+    let err = app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: now, end_time: now + 10 }, &[coin(100,"partner")]).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::TooManyActiveSchedules {})));
+
+    advance_seconds(&mut app, 10);
+    let now = app.block_info().time.seconds();
+    app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: now, end_time: now + 10 }, &[coin(100,"partner")]).unwrap();
+    let active: ActiveSchedulesResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::ActiveSchedules {}).unwrap();
+    assert_eq!(active.schedules.len(), 1);
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("alice") }).unwrap();
+    assert_eq!(pending.schedules, vec![SchedulePendingRewards { schedule_id: MAX_ACTIVE_SCHEDULES as u64, rewards: coin(0,"partner") }]);
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(1000,"partner"), coin(0,"gov")]);
+
+    // The rewards of the ended schedules are still paid out
+    advance_seconds(&mut app, 10);
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Unbond { amount: Uint128::new(80) }, &[]).unwrap();
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("alice") }).unwrap();
+    assert!(pending.schedules.is_empty());
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(1100,"partner"), coin(0,"gov")]);
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Claim {}, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance("alice", "partner").unwrap().amount, Uint128::new(1100));
+
+    // Only the owner sets the schedule denoms and their minimum funding, up to the maximum denoms
+    app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::SetScheduleDenom { denom: "other".to_string(), min_amount: Uint128::new(1) }, &[]).unwrap_err();
+    app.execute_contract(Addr::unchecked("owner"), incentives.clone(), &ExecuteMsg::SetScheduleDenom { denom: "gov".to_string(), min_amount: Uint128::new(1000) }, &[]).unwrap();
+    let now = app.block_info().time.seconds();
+    let err = app.execute_contract(partner.clone(), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: now, end_time: now + 10 }, &[coin(999,"gov")]).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InsufficientScheduleFunds {})));
+    for i in 2..MAX_SCHEDULE_DENOMS {
+        app.execute_contract(Addr::unchecked("owner"), incentives.clone(), &ExecuteMsg::SetScheduleDenom { denom: format!("other{i}"), min_amount: Uint128::new(1) }, &[]).unwrap();
+    }
+    let err = app.execute_contract(Addr::unchecked("owner"), incentives.clone(), &ExecuteMsg::SetScheduleDenom { denom: "other".to_string(), min_amount: Uint128::new(1) }, &[]).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::TooManyScheduleDenoms {})));
+}
+
+#[test]
+fn reward_schedules_are_pushed_back_while_nothing_is_bonded() {
+    let mut app = App::default();
+    let (incentives, lp_token) = setup_incentives(&mut app);
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "partner".to_string(),
+        amount: vec![coin(2000,"partner")],
+    }))
+        .unwrap();
+
+    // 100 partner per second for 10 seconds, of which nothing is bonded for the first 5
+    let now = app.block_info().time.seconds();
+    app.execute_contract(Addr::unchecked("partner"), incentives.clone(), &ExecuteMsg::CreateSchedule { start_time: now, end_time: now + 10 }, &[coin(1000,"partner")]).unwrap();
+    advance_seconds(&mut app, 5);
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+
+    // The schedule now ends 5 seconds later and pays out in full
+    let active: ActiveSchedulesResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::ActiveSchedules {}).unwrap();
+    assert_eq!((active.schedules[0].start_time, active.schedules[0].end_time), (now + 5, now + 15));
+    advance_seconds(&mut app, 5);
+    app.execute_contract(Addr::unchecked("alice"), incentives.clone(), &ExecuteMsg::Unbond { amount: Uint128::new(80) }, &[]).unwrap();
+    advance_seconds(&mut app, 20);
+    bond(&mut app, &lp_token, &incentives, "bob", 20).unwrap();
+    advance_seconds(&mut app, 10);
+
+    // Alice gets the first half and Bob, after another 20 seconds without bonds, the second
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("alice") }).unwrap();
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(500,"partner"), coin(0,"gov")]);
+    let pending: PendingRewardsResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::PendingRewards { user: Addr::unchecked("bob") }).unwrap();
+    assert_eq!(pending.ended_schedule_rewards, vec![coin(500,"partner"), coin(0,"gov")]);
+
+    // The longest schedule can be pushed back for as long as nothing is bonded
+    app.execute_contract(Addr::unchecked("bob"), incentives.clone(), &ExecuteMsg::Unbond { amount: Uint128::new(20) }, &[]).unwrap();
+    let now = app.block_info().time.seconds();
+    app.execute_contract(
+        Addr::unchecked("partner"),
+        incentives.clone(),
+        &ExecuteMsg::CreateSchedule { start_time: now + MAX_SCHEDULE_DURATION, end_time: now + 2 * MAX_SCHEDULE_DURATION },
+        &[coin(1000,"partner")],
+    ).unwrap();
+    advance_seconds(&mut app, 100 * MAX_SCHEDULE_DURATION);
+    bond(&mut app, &lp_token, &incentives, "alice", 80).unwrap();
+    let active: ActiveSchedulesResponse = app.wrap().query_wasm_smart(&incentives, &QueryMsg::ActiveSchedules {}).unwrap();
+    let now = app.block_info().time.seconds();
+    assert_eq!((active.schedules[0].start_time, active.schedules[0].end_time), (now, now + MAX_SCHEDULE_DURATION));
+}
@@ -0,0 +1,59 @@
+use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdResult, Deps, to_json_binary};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cw2::set_contract_version;
+use crate::error::ContractError;
+use crate::execute::{assert_minimum_receive, execute_swap_operation, execute_swap_operations};
+use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::query::query_simulate_swap_operations;
+
+const CONTRACT_NAME: &str = "amm-router";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError>  {
+    set_contract_version(deps.storage, format!("crates.io:{CONTRACT_NAME}"), CONTRACT_VERSION)?;
+    Ok(Response::new().add_attribute("method", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError>  {
+    match msg {
+        ExecuteMsg::ExecuteSwapOperations {
+            operations,
+            minimum_receive,
+            to,
+        } => execute_swap_operations(deps, env, info, operations, minimum_receive, to),
+
+        ExecuteMsg::ExecuteSwapOperation {
+            operation,
+            prev_balance,
+        } => execute_swap_operation(deps, env, info, operation, prev_balance),
+
+        ExecuteMsg::AssertMinimumReceive {
+            ask_denom,
+            prev_balance,
+            minimum_receive,
+            receiver,
+        } => assert_minimum_receive(deps, env, info, ask_denom, prev_balance, minimum_receive, receiver),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::SimulateSwapOperations { offer_amount, operations } => {
+            to_json_binary(&query_simulate_swap_operations(deps, offer_amount, operations)?)
+        }
+    }
+}
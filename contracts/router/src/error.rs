@@ -0,0 +1,26 @@
+use cosmwasm_std::{OverflowError, StdError, Uint128};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Overflow(#[from] OverflowError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("Invalid funds sent")]
+    InvalidFunds {},
+
+    #[error("Swap operations must be provided")]
+    MustProvideOperations {},
+
+    #[error("Each swap operation must offer the asset received from the previous one")]
+    InvalidOperations {},
+
+    #[error("Received {received}, less than the minimum of {minimum_receive}")]
+    MinimumReceiveNotMet { minimum_receive: Uint128, received: Uint128 },
+}
@@ -0,0 +1,137 @@
+use amm_contract::msg::ExecuteMsg as PoolExecuteMsg;
+use cosmwasm_std::{BankMsg, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response, to_json_binary, Uint128, WasmMsg};
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, SwapOperation};
+
+// Each operation has to offer the asset that the previous one asked for
+pub(crate) fn validate_operations(operations: &[SwapOperation]) -> Result<(), ContractError> {
+    if operations.is_empty() {
+        return Err(ContractError::MustProvideOperations {});
+    }
+    if operations.windows(2).any(|pair| pair[0].ask_denom != pair[1].offer_denom) {
+        return Err(ContractError::InvalidOperations {});
+    }
+    Ok(())
+}
+
+// Wrap a callback into a message the router sends to itself
+fn callback_msg(env: &Env, msg: &ExecuteMsg) -> Result<CosmosMsg, ContractError> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_json_binary(msg)?,
+        funds: vec![],
+    }))
+}
+
+pub fn execute_swap_operations(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operations: Vec<SwapOperation>,
+    minimum_receive: Option<Uint128>,
+    to: Option<String>,
+) -> Result<Response, ContractError> {
+    validate_operations(&operations)?;
+
+    // Only the offer asset of the first operation is sent
+    let offer_denom = &operations[0].offer_denom;
+    if info.funds.len() != 1 || info.funds[0].denom != *offer_denom || info.funds[0].amount.is_zero() {
+        return Err(ContractError::InvalidFunds {});
+    }
+
+    let receiver = match to {
+        Some(to) => deps.api.addr_validate(&to)?,
+        None => info.sender,
+    };
+    let ask_denom = operations[operations.len() - 1].ask_denom.clone();
+
+    // Anything the router held before, e.g. coins sent to it by mistake, stays out of the swaps. Each operation
+    // swaps its offer asset's balance above what it was before this transaction, which is what the previous
+    // operation received, and the final step sends what the last one received
+    let sent = &info.funds[0];
+    let prev_balance = |denom: &str| -> Result<Uint128, ContractError> {
+        let balance = deps.querier.query_balance(&env.contract.address, denom)?.amount;
+        Ok(if denom == sent.denom { balance.checked_sub(sent.amount)? } else { balance })
+    };
+    let mut messages = operations
+        .into_iter()
+        .map(|operation| {
+            let prev_balance = prev_balance(&operation.offer_denom)?;
+            callback_msg(&env, &ExecuteMsg::ExecuteSwapOperation { operation, prev_balance })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    messages.push(callback_msg(&env, &ExecuteMsg::AssertMinimumReceive {
+        prev_balance: prev_balance(&ask_denom)?,
+        ask_denom,
+        minimum_receive: minimum_receive.unwrap_or_default(),
+        receiver: receiver.to_string(),
+    })?);
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attribute("action", "execute_swap_operations")
+        .add_attribute("receiver", receiver))
+}
+
+pub fn execute_swap_operation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operation: SwapOperation,
+    prev_balance: Uint128,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // Swap only what the previous step received
+    let balance = deps.querier.query_balance(&env.contract.address, &operation.offer_denom)?.amount;
+    let offered_asset = Coin { denom: operation.offer_denom.clone(), amount: balance.checked_sub(prev_balance)? };
+    if offered_asset.amount.is_zero() {
+        return Err(ContractError::InvalidFunds {});
+    }
+
+    let swap_msg = WasmMsg::Execute {
+        contract_addr: deps.api.addr_validate(&operation.pool_addr)?.to_string(),
+        msg: to_json_binary(&PoolExecuteMsg::Swap {
+            offered_asset: offered_asset.clone(),
+            ask_denom: Some(operation.ask_denom.clone()),
+            referral_address: None,
+            referral_commission: None,
+        })?,
+        funds: vec![offered_asset.clone()],
+    };
+
+    Ok(Response::new()
+        .add_message(swap_msg)
+        .add_attribute("action", "execute_swap_operation")
+        .add_attribute("pool", operation.pool_addr)
+        .add_attribute("offered_asset", offered_asset.to_string()))
+}
+
+pub fn assert_minimum_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    ask_denom: String,
+    prev_balance: Uint128,
+    minimum_receive: Uint128,
+    receiver: String,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let received = deps.querier.query_balance(&env.contract.address, &ask_denom)?.amount.checked_sub(prev_balance)?;
+    if received < minimum_receive {
+        return Err(ContractError::MinimumReceiveNotMet { minimum_receive, received });
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: receiver,
+            amount: vec![Coin { denom: ask_denom, amount: received }],
+        })
+        .add_attribute("action", "assert_minimum_receive")
+        .add_attribute("received", received))
+}
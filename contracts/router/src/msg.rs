@@ -0,0 +1,52 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+/// A swap of `offer_denom` for `ask_denom` in a single pool
+#[cw_serde]
+pub struct SwapOperation {
+    pub pool_addr: String,
+    pub offer_denom: String,
+    pub ask_denom: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Swap the funds sent through each pool in turn and send the last ask asset to `to`,
+    /// or back to the sender. Fails if less than `minimum_receive` comes out of the last pool.
+    ExecuteSwapOperations {
+        operations: Vec<SwapOperation>,
+        minimum_receive: Option<Uint128>,
+        to: Option<String>,
+    },
+    /// Callback: swap the router's balance of the offer asset above `prev_balance`, i.e. what the
+    /// previous operation received, in the operation's pool
+    ExecuteSwapOperation {
+        operation: SwapOperation,
+        prev_balance: Uint128,
+    },
+    /// Callback: send the router's balance of the last ask asset above `prev_balance`, i.e. what the
+    /// last operation received, to the receiver
+    AssertMinimumReceive {
+        ask_denom: String,
+        prev_balance: Uint128,
+        minimum_receive: Uint128,
+        receiver: String,
+    },
+}
+
+#[cw_serde]
+pub enum QueryMsg {
+    /// Amount of the last ask asset received for `offer_amount` of the first offer asset
+    SimulateSwapOperations {
+        offer_amount: Uint128,
+        operations: Vec<SwapOperation>,
+    },
+}
+
+#[cw_serde]
+pub struct SimulateSwapOperationsResponse {
+    pub amount: Uint128,
+}
@@ -0,0 +1,27 @@
+use amm_contract::msg::{QueryMsg as PoolQueryMsg, SimulateSwapResponse};
+use cosmwasm_std::{coin, Deps, StdError, StdResult, Uint128};
+use crate::execute::validate_operations;
+use crate::msg::{SimulateSwapOperationsResponse, SwapOperation};
+
+pub fn query_simulate_swap_operations(
+    deps: Deps,
+    offer_amount: Uint128,
+    operations: Vec<SwapOperation>,
+) -> StdResult<SimulateSwapOperationsResponse> {
+    validate_operations(&operations).map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    // Each pool is simulated with the amount received from the previous one
+    let mut amount = offer_amount;
+    for operation in operations {
+        let simulation: SimulateSwapResponse = deps.querier.query_wasm_smart(
+            operation.pool_addr,
+            &PoolQueryMsg::SimulateSwap {
+                offered_asset: coin(amount.u128(), operation.offer_denom),
+                ask_denom: Some(operation.ask_denom),
+            },
+        )?;
+        amount = simulation.return_asset.amount;
+    }
+
+    Ok(SimulateSwapOperationsResponse { amount })
+}
@@ -0,0 +1,193 @@
+use cosmwasm_std::{Addr, coin, Decimal, Uint128};
+use cw_multi_test::{App, BankSudo, ContractWrapper, Executor, SudoMsg};
+use amm_contract::msg::{Config, InstantiateMsg as PoolInstantiateMsg, PoolType};
+use amm_router::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation};
+
+// Router and the asset1/asset2 and asset2/asset3 pools
+fn setup_router(app: &mut App) -> (Addr, Addr, Addr) {
+    let pool_code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let pool_code_id = app.store_code(Box::new(pool_code));
+
+    let router_code = ContractWrapper::new(
+        amm_router::contract::execute,
+        amm_router::contract::instantiate,
+        amm_router::contract::query,
+    );
+    let router_code_id = app.store_code(Box::new(router_code));
+
+    for user in ["owner", "trader"] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2"), coin(100000000,"asset3")],
+        }))
+            .unwrap();
+    }
+
+    let mut pools = vec![];
+    for denoms in [["asset1", "asset2"], ["asset2", "asset3"]] {
+        let funding = vec![coin(100000, denoms[0]), coin(100000, denoms[1])];
+        pools.push(app.instantiate_contract(
+            pool_code_id,
+            Addr::unchecked("owner"),
+            &PoolInstantiateMsg {
+                initial_funding: funding.clone(),
+                initial_lp_token_supply: Uint128::new(100),
+                owner: None,
+                contract_config: Config {
+                    lp_token_addr: "lp_tokens".to_string(),
+                    fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                    tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                    pool_type: PoolType::ConstantProduct,
+                    position_nft_addr: None,
+                    dynamic_fee: None,
+                    max_referral_commission: Decimal::zero(),
+                },
+            },
+            &funding,
+            "mock-amm-contract",
+            None,
+        ).unwrap());
+    }
+
+    let router = app.instantiate_contract(
+        router_code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {},
+        &[],
+        "mock-amm-router",
+        None,
+    ).unwrap();
+
+    (router, pools[0].clone(), pools[1].clone())
+}
+
+fn operations(pool1: &Addr, pool2: &Addr) -> Vec<SwapOperation> {
+    vec![
+        SwapOperation { pool_addr: pool1.to_string(), offer_denom: "asset1".to_string(), ask_denom: "asset2".to_string() },
+        SwapOperation { pool_addr: pool2.to_string(), offer_denom: "asset2".to_string(), ask_denom: "asset3".to_string() },
+    ]
+}
+
+#[test]
+fn multi_hop_swap_matches_simulation() {
+    let mut app = App::default();
+    let (router, pool1, pool2) = setup_router(&mut app);
+
+    let simulation: SimulateSwapOperationsResponse = app.wrap().query_wasm_smart(router.clone(), &QueryMsg::SimulateSwapOperations {
+        offer_amount: Uint128::new(1000),
+        operations: operations(&pool1, &pool2),
+    }).unwrap();
+
+    app.execute_contract(
+        Addr::unchecked("trader"),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: operations(&pool1, &pool2),
+            minimum_receive: Some(simulation.amount),
+            to: Some("receiver".to_string()),
+        },
+        &[coin(1000,"asset1")],
+    ).unwrap();
+
+    // 1000 asset1 -> 987 asset2 -> 974 asset3, all sent to the receiver
+    let received = app.wrap().query_balance("receiver", "asset3").unwrap();
+    assert_eq!(received.amount, simulation.amount);
+    assert_eq!(received.amount, Uint128::new(974));
+
+    // Nothing is left in the router
+    assert!(app.wrap().query_all_balances(router).unwrap().is_empty());
+}
+
+#[test]
+fn multi_hop_swap_leaves_other_router_funds_alone() {
+    let mut app = App::default();
+    let (router, pool1, pool2) = setup_router(&mut app);
+
+    // Coins sent to the router by mistake, in every asset of the route
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: router.to_string(),
+        amount: vec![coin(500,"asset1"), coin(600,"asset2"), coin(700,"asset3")],
+    }))
+        .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked("trader"),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: operations(&pool1, &pool2),
+            minimum_receive: Some(Uint128::new(974)),
+            to: Some("receiver".to_string()),
+        },
+        &[coin(1000,"asset1")],
+    ).unwrap();
+
+    // Only the 1000 asset1 sent are swapped, and only their output is sent on
+    let received = app.wrap().query_balance("receiver", "asset3").unwrap();
+    assert_eq!(received.amount, Uint128::new(974));
+    assert_eq!(app.wrap().query_all_balances(router).unwrap(), vec![coin(500,"asset1"), coin(600,"asset2"), coin(700,"asset3")]);
+}
+
+#[test]
+fn multi_hop_swap_errors() {
+    let mut app = App::default();
+    let (router, pool1, pool2) = setup_router(&mut app);
+    let trader = Addr::unchecked("trader");
+
+    // Less than the minimum comes out of the last pool
+    app.execute_contract(
+        trader.clone(),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: operations(&pool1, &pool2),
+            minimum_receive: Some(Uint128::new(975)),
+            to: None,
+        },
+        &[coin(1000,"asset1")],
+    ).unwrap_err();
+
+    // The operations don't connect
+    let mut broken = operations(&pool1, &pool2);
+    broken.reverse();
+    app.execute_contract(
+        trader.clone(),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: broken,
+            minimum_receive: None,
+            to: None,
+        },
+        &[coin(1000,"asset2")],
+    ).unwrap_err();
+
+    // The funds don't match the first offer asset
+    app.execute_contract(
+        trader.clone(),
+        router.clone(),
+        &ExecuteMsg::ExecuteSwapOperations {
+            operations: operations(&pool1, &pool2),
+            minimum_receive: None,
+            to: None,
+        },
+        &[coin(1000,"asset2")],
+    ).unwrap_err();
+
+    // Callbacks can only come from the router itself
+    app.execute_contract(
+        trader.clone(),
+        router.clone(),
+        &ExecuteMsg::AssertMinimumReceive {
+            ask_denom: "asset3".to_string(),
+            prev_balance: Uint128::zero(),
+            minimum_receive: Uint128::zero(),
+            receiver: trader.to_string(),
+        },
+        &[],
+    ).unwrap_err();
+
+    let balance = app.wrap().query_balance(trader, "asset1").unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000000));
+}
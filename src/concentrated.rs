@@ -0,0 +1,741 @@
+use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, Decimal256, DepsMut, Env, Int128, MessageInfo, Order, OverflowError, OverflowOperation, Response, SignedDecimal256, StdError, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::Bound;
+use crate::error::ContractError;
+use crate::events::{collect_fees_event, deposit_event, swap_event, withdraw_event};
+use crate::fee_discount::discounted_fee_share;
+use crate::math::{checked_div, checked_div_ceil, checked_mul_ceil};
+use crate::msg::{Config, PoolType};
+use crate::state::{CONCENTRATED_POOL, ConcentratedPool, CONFIG, NEXT_POSITION_ID, POOL_RESERVES, PoolReserves, Position, POSITIONS, TickInfo, TICKS};
+use crate::stats::record_swap;
+
+/// Lowest tick a position can use, the price 1.0001^-200000 is about 2.06e-9
+pub const MIN_TICK: i32 = -200_000;
+
+/// Highest tick a position can use, the price 1.0001^200000 is about 4.85e8
+pub const MAX_TICK: i32 = 200_000;
+
+// 1.0001, the price ratio between two neighbouring ticks
+const TICK_BASE: Decimal256 = Decimal256::raw(1_000_100_000_000_000_000);
+
+/// Square root of the price at a tick: 1.0001^(tick / 2)
+pub fn sqrt_price_at_tick(tick: i32) -> StdResult<Decimal256> {
+    let sqrt_price = TICK_BASE.checked_pow(tick.unsigned_abs())?.sqrt();
+    if tick < 0 {
+        checked_div(Decimal256::one(), sqrt_price)
+    } else {
+        Ok(sqrt_price)
+    }
+}
+
+/// The highest tick whose square root price is at or below `sqrt_price`
+pub fn tick_at_sqrt_price(sqrt_price: Decimal256) -> StdResult<i32> {
+    let mut low = MIN_TICK;
+    let mut high = MAX_TICK;
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+fn to_decimal(amount: Uint128) -> Decimal256 {
+    Decimal256::from_ratio(amount, 1u128)
+}
+
+// Round a decimal token amount to a whole number of tokens
+fn to_amount(amount: Decimal256, round_up: bool) -> StdResult<Uint128> {
+    let amount = if round_up { amount.to_uint_ceil() } else { amount.to_uint_floor() };
+    Ok(Uint128::try_from(amount)?)
+}
+
+/// Amount of asset1 held by `liquidity` between two square root prices: L * (sb - sa) / (sa * sb)
+pub fn asset1_delta(sqrt_price_a: Decimal256, sqrt_price_b: Decimal256, liquidity: Uint128, round_up: bool) -> StdResult<Uint128> {
+    let (lower, upper) = if sqrt_price_a < sqrt_price_b { (sqrt_price_a, sqrt_price_b) } else { (sqrt_price_b, sqrt_price_a) };
+    let amount = checked_div(to_decimal(liquidity).checked_mul(upper.checked_sub(lower)?)?, lower.checked_mul(upper)?)?;
+    to_amount(amount, round_up)
+}
+
+/// Amount of asset2 held by `liquidity` between two square root prices: L * (sb - sa)
+pub fn asset2_delta(sqrt_price_a: Decimal256, sqrt_price_b: Decimal256, liquidity: Uint128, round_up: bool) -> StdResult<Uint128> {
+    let (lower, upper) = if sqrt_price_a < sqrt_price_b { (sqrt_price_a, sqrt_price_b) } else { (sqrt_price_b, sqrt_price_a) };
+    let amount = to_decimal(liquidity).checked_mul(upper.checked_sub(lower)?)?;
+    to_amount(amount, round_up)
+}
+
+/// Amounts of asset1 and asset2 represented by `liquidity` in the range [lower_tick, upper_tick)
+pub fn amounts_for_liquidity(
+    pool: &ConcentratedPool,
+    lower_tick: i32,
+    upper_tick: i32,
+    liquidity: Uint128,
+    round_up: bool,
+) -> StdResult<(Uint128, Uint128)> {
+    let sqrt_lower = sqrt_price_at_tick(lower_tick)?;
+    let sqrt_upper = sqrt_price_at_tick(upper_tick)?;
+
+    if pool.current_tick < lower_tick {
+        // Price below the range: the position is all asset1
+        Ok((asset1_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?, Uint128::zero()))
+    } else if pool.current_tick >= upper_tick {
+        // Price above the range: the position is all asset2
+        Ok((Uint128::zero(), asset2_delta(sqrt_lower, sqrt_upper, liquidity, round_up)?))
+    } else {
+        Ok((
+            asset1_delta(pool.sqrt_price, sqrt_upper, liquidity, round_up)?,
+            asset2_delta(sqrt_lower, pool.sqrt_price, liquidity, round_up)?,
+        ))
+    }
+}
+
+// L = x * sa * sb / (sb - sa)
+fn liquidity_for_asset1(sqrt_lower: Decimal256, sqrt_upper: Decimal256, amount: Uint128) -> StdResult<Decimal256> {
+    checked_div(
+        to_decimal(amount).checked_mul(sqrt_lower)?.checked_mul(sqrt_upper)?,
+        sqrt_upper.checked_sub(sqrt_lower)?,
+    )
+}
+
+// L = y / (sb - sa)
+fn liquidity_for_asset2(sqrt_lower: Decimal256, sqrt_upper: Decimal256, amount: Uint128) -> StdResult<Decimal256> {
+    checked_div(to_decimal(amount), sqrt_upper.checked_sub(sqrt_lower)?)
+}
+
+/// The largest liquidity in the range [lower_tick, upper_tick) that the given amounts can pay for
+pub fn liquidity_for_amounts(
+    pool: &ConcentratedPool,
+    lower_tick: i32,
+    upper_tick: i32,
+    amount1: Uint128,
+    amount2: Uint128,
+) -> StdResult<Uint128> {
+    let sqrt_lower = sqrt_price_at_tick(lower_tick)?;
+    let sqrt_upper = sqrt_price_at_tick(upper_tick)?;
+
+    let liquidity = if pool.current_tick < lower_tick {
+        liquidity_for_asset1(sqrt_lower, sqrt_upper, amount1)?
+    } else if pool.current_tick >= upper_tick || pool.sqrt_price >= sqrt_upper {
+        liquidity_for_asset2(sqrt_lower, sqrt_upper, amount2)?
+    } else {
+        std::cmp::min(
+            liquidity_for_asset1(pool.sqrt_price, sqrt_upper, amount1)?,
+            liquidity_for_asset2(sqrt_lower, pool.sqrt_price, amount2)?,
+        )
+    };
+    to_amount(liquidity, false)
+}
+
+fn apply_liquidity_delta(liquidity: Uint128, delta: i128) -> Result<Uint128, ContractError> {
+    if delta >= 0 {
+        Ok(liquidity.checked_add(Uint128::new(delta.unsigned_abs()))?)
+    } else {
+        Ok(liquidity.checked_sub(Uint128::new(delta.unsigned_abs()))?)
+    }
+}
+
+fn to_signed(value: Decimal256) -> StdResult<SignedDecimal256> {
+    SignedDecimal256::try_from(value).map_err(|_| StdError::generic_err("Fee growth out of range"))
+}
+
+// Add a position's liquidity change to one of its boundary ticks, initializing the tick if needed
+fn update_tick(
+    storage: &mut dyn Storage,
+    pool: &ConcentratedPool,
+    tick: i32,
+    liquidity_delta: i128,
+    is_upper: bool,
+) -> Result<(), ContractError> {
+    let mut info = match TICKS.may_load(storage, tick)? {
+        Some(info) => info,
+        // All fees so far are assumed to have been earned below a new tick at or below the current tick
+        None if tick <= pool.current_tick => TickInfo {
+            liquidity_gross: Uint128::zero(),
+            liquidity_net: Int128::zero(),
+            fee_growth_outside1: pool.fee_growth_global1,
+            fee_growth_outside2: pool.fee_growth_global2,
+        },
+        None => TickInfo {
+            liquidity_gross: Uint128::zero(),
+            liquidity_net: Int128::zero(),
+            fee_growth_outside1: Decimal256::zero(),
+            fee_growth_outside2: Decimal256::zero(),
+        },
+    };
+
+    info.liquidity_gross = apply_liquidity_delta(info.liquidity_gross, liquidity_delta)?;
+
+    // Crossing the lower tick upwards activates the position, crossing the upper tick deactivates it
+    let net_delta = if is_upper { -liquidity_delta } else { liquidity_delta };
+    info.liquidity_net = info.liquidity_net.checked_add(Int128::new(net_delta))?;
+
+    TICKS.save(storage, tick, &info)?;
+    Ok(())
+}
+
+// Fee growth per unit of liquidity earned inside [lower_tick, upper_tick)
+fn fee_growth_inside(
+    storage: &dyn Storage,
+    pool: &ConcentratedPool,
+    lower_tick: i32,
+    upper_tick: i32,
+) -> StdResult<(SignedDecimal256, SignedDecimal256)> {
+    let lower = TICKS.load(storage, lower_tick)?;
+    let upper = TICKS.load(storage, upper_tick)?;
+
+    let global1 = to_signed(pool.fee_growth_global1)?;
+    let global2 = to_signed(pool.fee_growth_global2)?;
+
+    let (below1, below2) = if pool.current_tick >= lower_tick {
+        (to_signed(lower.fee_growth_outside1)?, to_signed(lower.fee_growth_outside2)?)
+    } else {
+        (global1.checked_sub(to_signed(lower.fee_growth_outside1)?)?, global2.checked_sub(to_signed(lower.fee_growth_outside2)?)?)
+    };
+    let (above1, above2) = if pool.current_tick < upper_tick {
+        (to_signed(upper.fee_growth_outside1)?, to_signed(upper.fee_growth_outside2)?)
+    } else {
+        (global1.checked_sub(to_signed(upper.fee_growth_outside1)?)?, global2.checked_sub(to_signed(upper.fee_growth_outside2)?)?)
+    };
+
+    Ok((
+        global1.checked_sub(below1)?.checked_sub(above1)?,
+        global2.checked_sub(below2)?.checked_sub(above2)?,
+    ))
+}
+
+// Fees earned by `liquidity` while the fee growth inside its range went from `last` to `current`
+fn fees_earned(liquidity: Uint128, current: SignedDecimal256, last: SignedDecimal256) -> StdResult<Uint128> {
+    let growth = current.checked_sub(last)?;
+    if growth <= SignedDecimal256::zero() {
+        return Ok(Uint128::zero());
+    }
+    let growth = Decimal256::try_from(growth).map_err(|_| StdError::generic_err("Fee growth out of range"))?;
+    Ok(Uint128::try_from(Uint256::from(liquidity).mul_floor(growth))?)
+}
+
+// Change the liquidity of a position, crediting the fees it earned since its last update
+fn update_position(
+    storage: &mut dyn Storage,
+    pool: &mut ConcentratedPool,
+    position: &mut Position,
+    liquidity_delta: i128,
+) -> Result<(), ContractError> {
+    update_tick(storage, pool, position.lower_tick, liquidity_delta, false)?;
+    update_tick(storage, pool, position.upper_tick, liquidity_delta, true)?;
+
+    let (inside1, inside2) = fee_growth_inside(storage, pool, position.lower_tick, position.upper_tick)?;
+    position.tokens_owed1 = position.tokens_owed1.checked_add(fees_earned(position.liquidity, inside1, position.fee_growth_inside1_last)?)?;
+    position.tokens_owed2 = position.tokens_owed2.checked_add(fees_earned(position.liquidity, inside2, position.fee_growth_inside2_last)?)?;
+    position.fee_growth_inside1_last = inside1;
+    position.fee_growth_inside2_last = inside2;
+    position.liquidity = apply_liquidity_delta(position.liquidity, liquidity_delta)?;
+
+    // The active liquidity only changes if the position range contains the current tick
+    if position.lower_tick <= pool.current_tick && pool.current_tick < position.upper_tick {
+        pool.liquidity = apply_liquidity_delta(pool.liquidity, liquidity_delta)?;
+    }
+
+    // Remove the ticks that no position uses anymore
+    for tick in [position.lower_tick, position.upper_tick] {
+        if TICKS.load(storage, tick)?.liquidity_gross.is_zero() {
+            TICKS.remove(storage, tick);
+        }
+    }
+    Ok(())
+}
+
+fn tick_spacing(config: &Config) -> Result<u32, ContractError> {
+    match config.pool_type {
+        PoolType::Concentrated { tick_spacing } => Ok(tick_spacing),
+        _ => Err(ContractError::InvalidPoolType {}),
+    }
+}
+
+fn validate_ticks(lower_tick: i32, upper_tick: i32, tick_spacing: u32) -> Result<(), ContractError> {
+    let spacing = i32::try_from(tick_spacing).map_err(|_| ContractError::InvalidTickRange {})?;
+    if spacing == 0
+        || lower_tick >= upper_tick
+        || lower_tick < MIN_TICK
+        || upper_tick > MAX_TICK
+        || lower_tick % spacing != 0
+        || upper_tick % spacing != 0 {
+        return Err(ContractError::InvalidTickRange {});
+    }
+    Ok(())
+}
+
+// Validate that the funds sent match the pool assets and the non-zero amounts of the deposit
+fn validate_funds(info: &MessageInfo, pool_reserves: &PoolReserves, asset1: &Coin, asset2: &Coin) -> Result<(), ContractError> {
+    if asset1.denom != pool_reserves.assets[0].denom || asset2.denom != pool_reserves.assets[1].denom {
+        return Err(ContractError::InvalidFunds {});
+    }
+    let expected: Vec<&Coin> = [asset1, asset2].into_iter().filter(|coin| !coin.amount.is_zero()).collect();
+    if expected.is_empty() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    if info.funds.len() != expected.len() || !expected.iter().all(|coin| info.funds.contains(coin)) {
+        return Err(ContractError::InvalidFunds {});
+    }
+    Ok(())
+}
+
+// Bank messages sending back the part of a deposit that a position didn't use
+fn refund_msgs(recipient: &Addr, provided: [&Coin; 2], used: [Uint128; 2]) -> Result<Vec<CosmosMsg>, ContractError> {
+    let mut refund = vec![];
+    for (coin, used) in provided.into_iter().zip(used) {
+        let amount = coin.amount.checked_sub(used)?;
+        if !amount.is_zero() {
+            refund.push(Coin { denom: coin.denom.clone(), amount });
+        }
+    }
+    if refund.is_empty() {
+        return Ok(vec![]);
+    }
+    Ok(vec![CosmosMsg::Bank(BankMsg::Send { to_address: recipient.to_string(), amount: refund })])
+}
+
+/// Set the starting price of a concentrated liquidity pool from the initial funding and give the
+/// funding to `owner` as a full range position. The reserves are set to the amounts the position uses.
+pub fn initialize_pool(
+    storage: &mut dyn Storage,
+    owner: &Addr,
+    reserves: &mut PoolReserves,
+    tick_spacing: u32,
+) -> Result<(), ContractError> {
+    let spacing = i32::try_from(tick_spacing).map_err(|_| ContractError::InvalidTickRange {})?;
+    if spacing == 0 {
+        return Err(ContractError::InvalidTickRange {});
+    }
+    let lower_tick = MIN_TICK / spacing * spacing;
+    let upper_tick = MAX_TICK / spacing * spacing;
+
+    let price = Decimal256::checked_from_ratio(reserves.assets[1].amount, reserves.assets[0].amount)
+        .map_err(|_| ContractError::InvalidInitialFunds {})?;
+    let sqrt_price = price.sqrt();
+    let mut pool = ConcentratedPool {
+        sqrt_price,
+        current_tick: tick_at_sqrt_price(sqrt_price)?,
+        liquidity: Uint128::zero(),
+        fee_growth_global1: Decimal256::zero(),
+        fee_growth_global2: Decimal256::zero(),
+    };
+
+    let liquidity = liquidity_for_amounts(&pool, lower_tick, upper_tick, reserves.assets[0].amount, reserves.assets[1].amount)?;
+    let liquidity_delta = i128::try_from(liquidity.u128()).map_err(|_| ContractError::InvalidInitialFunds {})?;
+
+    let mut position = new_position(lower_tick, upper_tick);
+    update_position(storage, &mut pool, &mut position, liquidity_delta)?;
+
+    let (amount1, amount2) = amounts_for_liquidity(&pool, lower_tick, upper_tick, liquidity, true)?;
+    reserves.assets[0].amount = std::cmp::min(amount1, reserves.assets[0].amount);
+    reserves.assets[1].amount = std::cmp::min(amount2, reserves.assets[1].amount);
+
+    POSITIONS.save(storage, (owner, 0), &position)?;
+    NEXT_POSITION_ID.save(storage, &1)?;
+    CONCENTRATED_POOL.save(storage, &pool)?;
+    Ok(())
+}
+
+fn new_position(lower_tick: i32, upper_tick: i32) -> Position {
+    Position {
+        lower_tick,
+        upper_tick,
+        liquidity: Uint128::zero(),
+        fee_growth_inside1_last: SignedDecimal256::zero(),
+        fee_growth_inside2_last: SignedDecimal256::zero(),
+        tokens_owed1: Uint128::zero(),
+        tokens_owed2: Uint128::zero(),
+    }
+}
+
+pub fn create_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    lower_tick: i32,
+    upper_tick: i32,
+    asset1: Coin,
+    asset2: Coin,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    validate_ticks(lower_tick, upper_tick, tick_spacing(&config)?)?;
+
+    let position_id = NEXT_POSITION_ID.may_load(deps.storage)?.unwrap_or_default();
+    NEXT_POSITION_ID.save(deps.storage, &position_id.checked_add(1).ok_or(ContractError::Overflow(OverflowError::new(OverflowOperation::Add, position_id, 1)))?)?;
+
+    add_liquidity(deps, info, position_id, new_position(lower_tick, upper_tick), asset1, asset2, "create_position")
+}
+
+pub fn increase_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+    asset1: Coin,
+    asset2: Coin,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    tick_spacing(&config)?;
+
+    let position = POSITIONS.load(deps.storage, (&info.sender, position_id))?;
+    add_liquidity(deps, info, position_id, position, asset1, asset2, "increase_liquidity")
+}
+
+// Add as much liquidity to the position as the deposited amounts allow and refund the rest
+fn add_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+    mut position: Position,
+    asset1: Coin,
+    asset2: Coin,
+    action: &str,
+) -> Result<Response, ContractError> {
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    validate_funds(&info, &pool_reserves, &asset1, &asset2)?;
+
+    let mut pool = CONCENTRATED_POOL.load(deps.storage)?;
+
+    let liquidity = liquidity_for_amounts(&pool, position.lower_tick, position.upper_tick, asset1.amount, asset2.amount)?;
+    if liquidity.is_zero() {
+        return Err(ContractError::DepositTooSmall {});
+    }
+    let liquidity_delta = i128::try_from(liquidity.u128()).map_err(|_| ContractError::InvalidFunds {})?;
+
+    update_position(deps.storage, &mut pool, &mut position, liquidity_delta)?;
+
+    // The deposit is rounded up in favor of the pool but can never exceed what was sent
+    let (amount1, amount2) = amounts_for_liquidity(&pool, position.lower_tick, position.upper_tick, liquidity, true)?;
+    let amount1 = std::cmp::min(amount1, asset1.amount);
+    let amount2 = std::cmp::min(amount2, asset2.amount);
+
+    pool_reserves.assets[0].amount = pool_reserves.assets[0].amount.checked_add(amount1)?;
+    pool_reserves.assets[1].amount = pool_reserves.assets[1].amount.checked_add(amount2)?;
+
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
+    CONCENTRATED_POOL.save(deps.storage, &pool)?;
+    POSITIONS.save(deps.storage, (&info.sender, position_id), &position)?;
+
+    let deposited = position_coins(&pool_reserves, amount1, amount2);
+    let event = deposit_event(&info.sender, &info.sender, &deposited, liquidity, &pool_reserves)
+        .add_attribute("position_id", position_id.to_string());
+
+    Ok(Response::new()
+        .add_messages(refund_msgs(&info.sender, [&asset1, &asset2], [amount1, amount2])?)
+        .add_event(event)
+        .add_attribute("action", action)
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("liquidity", liquidity.to_string())
+        .add_attribute("deposited_asset1", amount1.to_string())
+        .add_attribute("deposited_asset2", amount2.to_string()))
+}
+
+pub fn decrease_liquidity(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+    liquidity: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    tick_spacing(&config)?;
+
+    if liquidity.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    let mut position = POSITIONS.load(deps.storage, (&info.sender, position_id))?;
+    if liquidity > position.liquidity {
+        return Err(ContractError::WithdrawError {});
+    }
+    let liquidity_delta = i128::try_from(liquidity.u128()).map_err(|_| ContractError::WithdrawError {})?;
+
+    let mut pool = CONCENTRATED_POOL.load(deps.storage)?;
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+
+    update_position(deps.storage, &mut pool, &mut position, -liquidity_delta)?;
+
+    // Withdrawals are rounded down in favor of the pool
+    let (amount1, amount2) = amounts_for_liquidity(&pool, position.lower_tick, position.upper_tick, liquidity, false)?;
+
+    pool_reserves.assets[0].amount = pool_reserves.assets[0].amount.checked_sub(amount1)?;
+    pool_reserves.assets[1].amount = pool_reserves.assets[1].amount.checked_sub(amount2)?;
+
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
+    CONCENTRATED_POOL.save(deps.storage, &pool)?;
+    // The position is kept until its fees are collected
+    POSITIONS.save(deps.storage, (&info.sender, position_id), &position)?;
+
+    let withdrawn = position_coins(&pool_reserves, amount1, amount2);
+    let event = withdraw_event(&info.sender, &info.sender, &withdrawn, liquidity, &pool_reserves)
+        .add_attribute("position_id", position_id.to_string());
+
+    let response = Response::new().add_messages(send_msg(&info.sender, &withdrawn)).add_event(event);
+
+    Ok(response
+        .add_attribute("action", "decrease_liquidity")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("liquidity", liquidity.to_string())
+        .add_attribute("withdrawn_asset1", amount1.to_string())
+        .add_attribute("withdrawn_asset2", amount2.to_string()))
+}
+
+pub fn collect_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    position_id: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    tick_spacing(&config)?;
+
+    let mut position = POSITIONS.load(deps.storage, (&info.sender, position_id))?;
+    let mut pool = CONCENTRATED_POOL.load(deps.storage)?;
+
+    // Credit the fees earned up to now
+    if !position.liquidity.is_zero() {
+        update_position(deps.storage, &mut pool, &mut position, 0)?;
+        CONCENTRATED_POOL.save(deps.storage, &pool)?;
+    }
+
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let fees = position_coins(&pool_reserves, position.tokens_owed1, position.tokens_owed2);
+
+    let collected1 = position.tokens_owed1;
+    let collected2 = position.tokens_owed2;
+    position.tokens_owed1 = Uint128::zero();
+    position.tokens_owed2 = Uint128::zero();
+
+    // An empty position has nothing left to earn
+    if position.liquidity.is_zero() {
+        POSITIONS.remove(deps.storage, (&info.sender, position_id));
+    } else {
+        POSITIONS.save(deps.storage, (&info.sender, position_id), &position)?;
+    }
+
+    let response = Response::new()
+        .add_messages(send_msg(&info.sender, &fees))
+        .add_event(collect_fees_event(&info.sender, &info.sender, position_id, &fees, &pool_reserves));
+
+    Ok(response
+        .add_attribute("action", "collect_fees")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("collected_asset1", collected1.to_string())
+        .add_attribute("collected_asset2", collected2.to_string()))
+}
+
+// Amounts of the two pool assets as coins, including zero amounts so that events always list both
+fn position_coins(pool_reserves: &PoolReserves, amount1: Uint128, amount2: Uint128) -> Vec<Coin> {
+    vec![
+        Coin { denom: pool_reserves.assets[0].denom.clone(), amount: amount1 },
+        Coin { denom: pool_reserves.assets[1].denom.clone(), amount: amount2 },
+    ]
+}
+
+// Bank message sending the nonzero coins, if any
+fn send_msg(recipient: &Addr, coins: &[Coin]) -> Option<BankMsg> {
+    let amount: Vec<Coin> = coins.iter().filter(|coin| !coin.amount.is_zero()).cloned().collect();
+    (!amount.is_empty()).then(|| BankMsg::Send { to_address: recipient.to_string(), amount })
+}
+
+// The closest initialized tick in the swap direction. Going down includes the current tick
+fn next_initialized_tick(storage: &dyn Storage, current_tick: i32, zero_for_one: bool) -> StdResult<Option<i32>> {
+    let mut ticks = if zero_for_one {
+        TICKS.keys(storage, None, Some(Bound::inclusive(current_tick)), Order::Descending)
+    } else {
+        TICKS.keys(storage, Some(Bound::exclusive(current_tick)), None, Order::Ascending)
+    };
+    ticks.next().transpose()
+}
+
+// Move the active liquidity across a tick and flip its outside fee growth
+fn cross_tick(storage: &dyn Storage, pool: &mut ConcentratedPool, tick: i32, zero_for_one: bool) -> Result<TickInfo, ContractError> {
+    let mut info = TICKS.load(storage, tick)?;
+    info.fee_growth_outside1 = pool.fee_growth_global1.checked_sub(info.fee_growth_outside1)?;
+    info.fee_growth_outside2 = pool.fee_growth_global2.checked_sub(info.fee_growth_outside2)?;
+
+    let liquidity_delta = if zero_for_one { Int128::zero().checked_sub(info.liquidity_net)? } else { info.liquidity_net };
+    pool.liquidity = apply_liquidity_delta(pool.liquidity, liquidity_delta.i128())?;
+    Ok(info)
+}
+
+struct SwapStep {
+    next_sqrt_price: Decimal256,
+    amount_in: Decimal256,
+    amount_out: Decimal256,
+    fee_amount: Decimal256,
+}
+
+// Swap within a single tick range, stopping at the target price if the remaining input gets there
+fn swap_step(
+    sqrt_price: Decimal256,
+    target_sqrt_price: Decimal256,
+    liquidity: Uint128,
+    remaining: Decimal256,
+    fee_share: Decimal256,
+) -> StdResult<SwapStep> {
+    let liquidity = to_decimal(liquidity);
+    let zero_for_one = target_sqrt_price <= sqrt_price;
+    let net_share = Decimal256::one().checked_sub(fee_share)?;
+    let available = remaining.checked_mul(net_share)?;
+
+    // Input (after fees) needed to move the price to the target, rounded up. The rounding always favors the pool:
+    // the input and fee round up, the output down, and the price moves at least as far as the input takes it
+    let max_in = if zero_for_one {
+        checked_div_ceil(checked_mul_ceil(liquidity, sqrt_price.checked_sub(target_sqrt_price)?)?, sqrt_price.checked_mul(target_sqrt_price)?)?
+    } else {
+        checked_mul_ceil(liquidity, target_sqrt_price.checked_sub(sqrt_price)?)?
+    };
+
+    let (next_sqrt_price, amount_in, fee_amount) = if available >= max_in {
+        (target_sqrt_price, max_in, checked_div_ceil(checked_mul_ceil(max_in, fee_share)?, net_share)?)
+    } else if zero_for_one {
+        // L * sp / (L + in * sp) rounded up, so the price doesn't drop further than the input pays for
+        let next = checked_div_ceil(checked_mul_ceil(liquidity, sqrt_price)?, liquidity.checked_add(available.checked_mul(sqrt_price)?)?)?;
+        (std::cmp::max(next, target_sqrt_price), available, remaining.checked_sub(available)?)
+    } else {
+        let next = sqrt_price.checked_add(checked_div(available, liquidity)?)?;
+        (std::cmp::min(next, target_sqrt_price), available, remaining.checked_sub(available)?)
+    };
+
+    let amount_out = if zero_for_one {
+        liquidity.checked_mul(sqrt_price.checked_sub(next_sqrt_price)?)?
+    } else {
+        checked_div(liquidity.checked_mul(next_sqrt_price.checked_sub(sqrt_price)?)?, checked_mul_ceil(sqrt_price, next_sqrt_price)?)?
+    };
+
+    Ok(SwapStep { next_sqrt_price, amount_in, amount_out, fee_amount })
+}
+
+// Outcome of a swap through the tick ranges, without any change to storage
+struct SwapOutcome {
+    pool: ConcentratedPool,
+    crossed_ticks: Vec<(i32, TickInfo)>,
+    zero_for_one: bool,
+    amount_in: Uint128,
+    output_amount: Uint128,
+    fee_amount: Uint128,
+}
+
+// Swap through the tick ranges of a concentrated liquidity pool until the offered amount is used up,
+// charging `fee_share`
+fn compute_swap(
+    storage: &dyn Storage,
+    pool_reserves: &PoolReserves,
+    fee_share: Decimal,
+    offered_asset: &Coin,
+) -> Result<SwapOutcome, ContractError> {
+    let mut pool = CONCENTRATED_POOL.load(storage)?;
+
+    // Offering asset1 moves the price (asset2 per asset1) down, offering asset2 moves it up
+    let zero_for_one = if offered_asset.denom == pool_reserves.assets[0].denom {
+        true
+    } else if offered_asset.denom == pool_reserves.assets[1].denom {
+        false
+    } else {
+        return Err(ContractError::InvalidFunds {});
+    };
+
+    let fee_share = Decimal256::from(fee_share);
+    let mut remaining = to_decimal(offered_asset.amount);
+    let mut total_in = Decimal256::zero();
+    let mut total_out = Decimal256::zero();
+    let mut total_fee = Decimal256::zero();
+    let mut crossed_ticks = vec![];
+
+    while !remaining.is_zero() {
+        let next_tick = next_initialized_tick(storage, pool.current_tick, zero_for_one)?;
+        let target_tick = next_tick.unwrap_or(if zero_for_one { MIN_TICK } else { MAX_TICK });
+        let target_sqrt_price = sqrt_price_at_tick(target_tick)?;
+
+        let step = swap_step(pool.sqrt_price, target_sqrt_price, pool.liquidity, remaining, fee_share)?;
+        remaining = remaining.saturating_sub(step.amount_in.checked_add(step.fee_amount)?);
+        total_in = total_in.checked_add(step.amount_in)?;
+        total_out = total_out.checked_add(step.amount_out)?;
+        total_fee = total_fee.checked_add(step.fee_amount)?;
+
+        // The fee is shared by the liquidity that was active during the step
+        if !pool.liquidity.is_zero() {
+            let growth = checked_div(step.fee_amount, to_decimal(pool.liquidity))?;
+            if zero_for_one {
+                pool.fee_growth_global1 = pool.fee_growth_global1.checked_add(growth)?;
+            } else {
+                pool.fee_growth_global2 = pool.fee_growth_global2.checked_add(growth)?;
+            }
+        }
+
+        pool.sqrt_price = step.next_sqrt_price;
+        if step.next_sqrt_price == target_sqrt_price {
+            match next_tick {
+                Some(tick) => {
+                    crossed_ticks.push((tick, cross_tick(storage, &mut pool, tick, zero_for_one)?));
+                    pool.current_tick = if zero_for_one { tick - 1 } else { tick };
+                }
+                // The price reached the end of the tick range without using up the offered amount
+                None if !remaining.is_zero() => return Err(ContractError::InsufficientLiquidity {}),
+                None => pool.current_tick = tick_at_sqrt_price(pool.sqrt_price)?,
+            }
+        } else {
+            pool.current_tick = tick_at_sqrt_price(pool.sqrt_price)?;
+        }
+    }
+
+    Ok(SwapOutcome {
+        pool,
+        crossed_ticks,
+        zero_for_one,
+        amount_in: std::cmp::min(to_amount(total_in, true)?, offered_asset.amount),
+        output_amount: to_amount(total_out, false)?,
+        fee_amount: to_amount(total_fee, false)?,
+    })
+}
+
+/// Amount received and fee paid for swapping the offered asset, without executing the swap
+pub fn simulate_swap(storage: &dyn Storage, config: &Config, offered_asset: &Coin) -> Result<(Uint128, Uint128), ContractError> {
+    let pool_reserves = POOL_RESERVES.load(storage)?;
+    let outcome = compute_swap(storage, &pool_reserves, config.fee_share, offered_asset)?;
+    Ok((outcome.output_amount, outcome.fee_amount))
+}
+
+pub fn swap(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: &Config,
+    offered_asset: Coin,
+) -> Result<Response, ContractError> {
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let fee_share = discounted_fee_share(deps.storage, &info.sender, config.fee_share)?;
+    let outcome = compute_swap(deps.storage, &pool_reserves, fee_share, &offered_asset)?;
+    let (amount_in, output_amount) = (outcome.amount_in, outcome.output_amount);
+
+    let offer = if outcome.zero_for_one { 0 } else { 1 };
+    let denom = if outcome.zero_for_one {
+        pool_reserves.assets[0].amount = pool_reserves.assets[0].amount.checked_add(amount_in)?;
+        pool_reserves.assets[1].amount = pool_reserves.assets[1].amount.checked_sub(output_amount)?;
+        pool_reserves.assets[1].denom.clone()
+    } else {
+        pool_reserves.assets[1].amount = pool_reserves.assets[1].amount.checked_add(amount_in)?;
+        pool_reserves.assets[0].amount = pool_reserves.assets[0].amount.checked_sub(output_amount)?;
+        pool_reserves.assets[0].denom.clone()
+    };
+
+    for (tick, info) in &outcome.crossed_ticks {
+        TICKS.save(deps.storage, *tick, info)?;
+    }
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
+    CONCENTRATED_POOL.save(deps.storage, &outcome.pool)?;
+    record_swap(deps.storage, &env, pool_reserves.assets.len(), offer, offered_asset.amount, outcome.fee_amount)?;
+
+    let ask_asset = Coin { denom, amount: output_amount };
+    let send_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![ask_asset.clone()],
+    };
+
+    Ok(Response::new()
+        .add_message(CosmosMsg::Bank(send_msg))
+        .add_event(swap_event(&info.sender, &info.sender, &offered_asset, &ask_asset, outcome.fee_amount, Uint128::zero(), &pool_reserves))
+        .add_attribute("action", "swap"))
+}
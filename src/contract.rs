@@ -1,10 +1,17 @@
-use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Response, StdResult, Deps, to_json_binary, entry_point};
-use cw2::set_contract_version;
+use cosmwasm_std::{Binary, DepsMut, Env, MessageInfo, Reply, Response, StdResult, Deps, to_json_binary};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cw2::{get_contract_version, set_contract_version};
+use crate::concentrated::{collect_fees, create_position, decrease_liquidity, increase_liquidity};
 use crate::error::ContractError;
-use crate::execute::{deposit, swap, withdraw};
+use crate::execute::{deposit, skim, swap, sync, withdraw};
+use crate::fee_discount::set_fee_discount;
+use crate::flash_loan::{flash_loan, flash_loan_reply, FLASH_LOAN_REPLY_ID};
 use crate::instantiate::set_up_contract;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::query::{query_config, query_reserves, query_user_share};
+use crate::migrate::migrate_contract;
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
+use crate::owner::update_owner;
+use crate::query::{query_all_shares, query_concentrated_pool, query_config, query_effective_fee, query_owner, query_position, query_pool_stats, query_position_nft, query_position_pnl, query_reserve_drift, query_reserves, query_share_value, query_simulate_deposit, query_simulate_swap, query_simulate_withdraw, query_user_share};
 
 const CONTRACT_NAME: &str = "xyk-amm";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -30,25 +37,107 @@ pub fn execute(
 ) -> Result<Response, ContractError>  {
     match msg {
         ExecuteMsg::Deposit {
-            asset1,
-            asset2,
-        } => deposit(deps, env, info, asset1, asset2),
+            assets,
+        } => deposit(deps, env, info, assets),
 
         ExecuteMsg::Withdraw {
             amount_to_burn,
-        } => withdraw(deps, env, info, amount_to_burn),
+            asset,
+            position_id,
+        } => withdraw(deps, env, info, amount_to_burn, asset, position_id),
 
         ExecuteMsg::Swap {
             offered_asset,
-        } => swap(deps, env, info, offered_asset),
+            ask_denom,
+            referral_address,
+            referral_commission,
+        } => swap(deps, env, info, offered_asset, ask_denom, referral_address, referral_commission),
+
+        ExecuteMsg::CreatePosition {
+            lower_tick,
+            upper_tick,
+            asset1,
+            asset2,
+        } => create_position(deps, info, lower_tick, upper_tick, asset1, asset2),
+
+        ExecuteMsg::IncreaseLiquidity {
+            position_id,
+            asset1,
+            asset2,
+        } => increase_liquidity(deps, info, position_id, asset1, asset2),
+
+        ExecuteMsg::DecreaseLiquidity {
+            position_id,
+            liquidity,
+        } => decrease_liquidity(deps, info, position_id, liquidity),
+
+        ExecuteMsg::CollectFees {
+            position_id,
+        } => collect_fees(deps, info, position_id),
+
+        ExecuteMsg::FlashLoan {
+            assets,
+            callback_msg,
+        } => flash_loan(deps, info, assets, callback_msg),
+
+        ExecuteMsg::Sync {} => sync(deps, env, info),
+
+        ExecuteMsg::Skim {
+            to,
+        } => skim(deps, env, info, to),
+
+        ExecuteMsg::SetFeeDiscount {
+            address,
+            discount,
+        } => set_fee_discount(deps, info, address, discount),
+
+        ExecuteMsg::UpdateOwner {
+            owner,
+        } => update_owner(deps, info, owner),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    let contract_name = format!("crates.io:{CONTRACT_NAME}");
+
+    // Only this contract can be migrated, from an older version
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != contract_name {
+        return Err(ContractError::InvalidMigration {});
+    }
+    let response = migrate_contract(deps.branch(), &env, &stored.version, CONTRACT_VERSION, msg)?;
+    set_contract_version(deps.storage, contract_name, CONTRACT_VERSION)?;
+
+    Ok(response)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match msg.id {
+        FLASH_LOAN_REPLY_ID => flash_loan_reply(deps, env),
+        _ => Err(ContractError::UnknownReplyId {}),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetReserves {} => to_json_binary(&query_reserves(deps)?),
         QueryMsg::GetUserShare { user } => to_json_binary(&query_user_share(deps, user)?),
+        QueryMsg::AllShares { start_after, limit } => to_json_binary(&query_all_shares(deps, start_after, limit)?),
         QueryMsg::GetConfig {} => to_json_binary(&query_config(deps)?),
+        QueryMsg::GetConcentratedPool {} => to_json_binary(&query_concentrated_pool(deps)?),
+        QueryMsg::GetPosition { owner, position_id } => to_json_binary(&query_position(deps, owner, position_id)?),
+        QueryMsg::SimulateSwap { offered_asset, ask_denom } => to_json_binary(&query_simulate_swap(deps, env, offered_asset, ask_denom)?),
+        QueryMsg::ReserveDrift {} => to_json_binary(&query_reserve_drift(deps, env)?),
+        QueryMsg::SimulateDeposit { assets } => to_json_binary(&query_simulate_deposit(deps, assets)?),
+        QueryMsg::ShareValue { user } => to_json_binary(&query_share_value(deps, user)?),
+        QueryMsg::SimulateWithdraw { lp_amount, asset } => to_json_binary(&query_simulate_withdraw(deps, lp_amount, asset)?),
+        QueryMsg::PositionNft { position_id } => to_json_binary(&query_position_nft(deps, position_id)?),
+        QueryMsg::PositionPnl { user } => to_json_binary(&query_position_pnl(deps, user)?),
+        QueryMsg::PoolStats {} => to_json_binary(&query_pool_stats(deps, env)?),
+        QueryMsg::EffectiveFee { address } => to_json_binary(&query_effective_fee(deps, env, address)?),
+        QueryMsg::Owner {} => to_json_binary(&query_owner(deps)?),
     }
 }
\ No newline at end of file
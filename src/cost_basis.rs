@@ -0,0 +1,53 @@
+use cosmwasm_std::{Addr, Coin, Decimal256, Storage, Uint128};
+use crate::error::ContractError;
+use crate::math::multiply_ratio;
+use crate::state::{COST_BASIS, CostBasis, PoolReserves};
+
+// Add a deposit to the cost basis of the depositor. The entry price is averaged by LP tokens
+pub(crate) fn record_deposit(
+    storage: &mut dyn Storage,
+    user: &Addr,
+    pool_reserves: &PoolReserves,
+    deposits: &[Uint128],
+    lp_amount: Uint128,
+    entry_price: Decimal256,
+) -> Result<(), ContractError> {
+    let mut cost_basis = COST_BASIS.may_load(storage, user)?.unwrap_or_else(|| CostBasis {
+        lp_amount: Uint128::zero(),
+        deposits: pool_reserves.assets.iter().map(|asset| Coin { denom: asset.denom.clone(), amount: Uint128::zero() }).collect(),
+        entry_price: Decimal256::zero(),
+    });
+
+    let total_lp_amount = cost_basis.lp_amount.checked_add(lp_amount)?;
+    cost_basis.entry_price = cost_basis.entry_price
+        .checked_mul(Decimal256::from_ratio(cost_basis.lp_amount, total_lp_amount))?
+        .checked_add(entry_price.checked_mul(Decimal256::from_ratio(lp_amount, total_lp_amount))?)?;
+    cost_basis.lp_amount = total_lp_amount;
+    for (deposited, amount) in cost_basis.deposits.iter_mut().zip(deposits) {
+        deposited.amount = deposited.amount.checked_add(*amount)?;
+    }
+
+    COST_BASIS.save(storage, user, &cost_basis)?;
+    Ok(())
+}
+
+// Take the withdrawn share out of the cost basis of the user, keeping the entry price.
+// LP tokens the user got from somewhere else than a deposit have no cost basis
+pub(crate) fn record_withdraw(storage: &mut dyn Storage, user: &Addr, lp_amount: Uint128) -> Result<(), ContractError> {
+    let Some(mut cost_basis) = COST_BASIS.may_load(storage, user)? else {
+        return Ok(());
+    };
+
+    let remaining = cost_basis.lp_amount.saturating_sub(lp_amount);
+    if remaining.is_zero() {
+        COST_BASIS.remove(storage, user);
+        return Ok(());
+    }
+
+    for deposited in cost_basis.deposits.iter_mut() {
+        deposited.amount = multiply_ratio(deposited.amount, remaining, cost_basis.lp_amount)?;
+    }
+    cost_basis.lp_amount = remaining;
+    COST_BASIS.save(storage, user, &cost_basis)?;
+    Ok(())
+}
@@ -0,0 +1,203 @@
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128, Uint256};
+use crate::error::ContractError;
+use crate::math::{checked_div, multiply_ratio, pow};
+use crate::msg::PoolType;
+
+/// Invariant math of a pool, kept free of storage so that it can be shared by the contract,
+/// simulation queries and off-chain tooling.
+/// Reserves, deposits and weights are indexed by asset position in the pool.
+///
+/// Rounding always favors the pool: amounts paid out (swap outputs, withdrawals) and LP tokens
+/// minted round down, amounts paid in (swap inputs, fees) round up.
+pub trait PoolCurve {
+    /// Amount of the ask asset paid out for `offer_amount` of the offer asset (after fees)
+    fn swap_out(&self, reserves: &[Uint128], offer: usize, ask: usize, offer_amount: Uint128) -> StdResult<Uint128>;
+
+    /// Amount of the offer asset (after fees) needed to receive `ask_amount` of the ask asset
+    fn swap_in(&self, reserves: &[Uint128], offer: usize, ask: usize, ask_amount: Uint128) -> StdResult<Uint128>;
+
+    /// Price of the offer asset in units of the ask asset
+    fn spot_price(&self, reserves: &[Uint128], offer: usize, ask: usize) -> StdResult<Decimal256>;
+
+    /// Share of the pool value held by each asset
+    fn weights(&self, asset_count: usize) -> Vec<Decimal256>;
+
+    /// Amount of the `counter` asset that keeps the pool price unchanged when deposited alongside `amount` of the `asset`
+    fn counter_deposit(&self, reserves: &[Uint128], asset: usize, amount: Uint128, counter: usize) -> StdResult<Uint128> {
+        multiply_ratio(amount, reserves[counter], reserves[asset])
+    }
+
+    /// LP tokens minted for a deposit, using the smallest proportional deposit:
+    /// liquidity_tokens = total_supply * min(deposit_x / reserve_x, deposit_y / reserve_y)
+    fn lp_for_deposit(&self, reserves: &[Uint128], deposits: &[Uint128], total_supply: Uint128) -> StdResult<Uint128> {
+        let mut lp_tokens: Option<Uint128> = None;
+        for (reserve, deposit) in reserves.iter().zip(deposits) {
+            let lp_for_asset = multiply_ratio(*deposit, total_supply, *reserve)?;
+            lp_tokens = Some(lp_tokens.map_or(lp_for_asset, |lp| std::cmp::min(lp, lp_for_asset)));
+        }
+        Ok(lp_tokens.unwrap_or_default())
+    }
+
+    /// LP tokens minted for a deposit of any subset of the assets, from the growth of the invariant:
+    /// liquidity_tokens = total_supply * (product((reserve_i + deposit_i) / reserve_i) ^ weight_i) - 1)
+    /// The part of each deposit above the proportional deposit is charged the swap fee, as it is
+    /// equivalent to a swap into the other assets.
+    fn lp_for_unbalanced_deposit(&self, reserves: &[Uint128], deposits: &[Uint128], total_supply: Uint128, fee_share: Decimal) -> StdResult<Uint128> {
+        let weights = self.weights(reserves.len());
+        let fee_share = Decimal256::from(fee_share);
+
+        let mut balance_ratios = vec![];
+        let mut weighted_ratio = Decimal256::zero();
+        for ((reserve, deposit), weight) in reserves.iter().zip(deposits).zip(&weights) {
+            let ratio = Decimal256::checked_from_ratio(reserve.checked_add(*deposit)?, *reserve)
+                .map_err(|_| StdError::generic_err("Empty pool reserve"))?;
+            weighted_ratio = weighted_ratio.checked_add(ratio.checked_mul(*weight)?)?;
+            balance_ratios.push(ratio);
+        }
+
+        let mut invariant_ratio = Decimal256::one();
+        for (i, ratio) in balance_ratios.iter().enumerate() {
+            let mut amount_in = Uint256::from(deposits[i]);
+            if *ratio > weighted_ratio {
+                let proportional = Uint256::from(reserves[i]).mul_floor(weighted_ratio.checked_sub(Decimal256::one())?);
+                let taxable = amount_in.checked_sub(proportional)?;
+                amount_in = proportional.checked_add(taxable.mul_floor(Decimal256::one().checked_sub(fee_share)?))?;
+            }
+            let amount_in = Uint128::try_from(amount_in)?;
+            let growth = Decimal256::checked_from_ratio(reserves[i].checked_add(amount_in)?, reserves[i])
+                .map_err(|_| StdError::generic_err("Empty pool reserve"))?;
+            invariant_ratio = invariant_ratio.checked_mul(pow(growth, weights[i])?)?;
+        }
+
+        let lp_tokens = Uint256::from(total_supply).mul_floor(invariant_ratio.checked_sub(Decimal256::one())?);
+        Ok(Uint128::try_from(lp_tokens)?)
+    }
+
+    /// Assets paid out for burning `lp_amount` LP tokens, proportional to the reserves
+    fn assets_for_withdraw(&self, reserves: &[Uint128], lp_amount: Uint128, total_supply: Uint128) -> StdResult<Vec<Uint128>> {
+        reserves
+            .iter()
+            .map(|reserve| multiply_ratio(*reserve, lp_amount, total_supply))
+            .collect()
+    }
+
+    /// Amount of a single asset paid out for burning `lp_amount` LP tokens, from the drop of the invariant:
+    /// amount = reserve * (1 - ((total_supply - lp_amount) / total_supply) ^ (1 / weight))
+    /// The part of the payout that comes from the other assets is charged the swap fee.
+    fn asset_for_single_withdraw(&self, reserves: &[Uint128], asset: usize, lp_amount: Uint128, total_supply: Uint128, fee_share: Decimal) -> StdResult<Uint128> {
+        let weight = self.weights(reserves.len())[asset];
+
+        let invariant_ratio = Decimal256::checked_from_ratio(total_supply.checked_sub(lp_amount)?, total_supply)
+            .map_err(|_| StdError::generic_err("Empty LP supply"))?;
+        let balance_ratio = pow(invariant_ratio, checked_div(Decimal256::one(), weight)?)?;
+        let amount_out = Uint256::from(reserves[asset]).mul_floor(Decimal256::one().checked_sub(balance_ratio)?);
+
+        let taxable = amount_out.mul_ceil(Decimal256::one().checked_sub(weight)?);
+        let fee = taxable.mul_ceil(Decimal256::from(fee_share));
+        Ok(Uint128::try_from(amount_out.checked_sub(fee)?)?)
+    }
+}
+
+/// Constant product pool (x * y = k). With more than two assets every pair trades on x * y = k,
+/// which keeps the product of all reserves constant
+pub struct ConstantProduct;
+
+impl PoolCurve for ConstantProduct {
+    fn swap_out(&self, reserves: &[Uint128], offer: usize, ask: usize, offer_amount: Uint128) -> StdResult<Uint128> {
+        // Calculate the invariant k before the swap, which needs up to 256 bits
+        let k = reserves[offer].full_mul(reserves[ask]);
+
+        // Calculate the new ask reserve such that new_offer_reserve * new_ask_reserve = k, rounded up
+        // so that the output rounds down and k never decreases
+        let new_offer_reserve = Uint256::from(reserves[offer]).checked_add(Uint256::from(offer_amount))?;
+        let new_ask_reserve = k.checked_add(new_offer_reserve.checked_sub(Uint256::one())?)?.checked_div(new_offer_reserve)?;
+
+        Ok(Uint128::try_from(Uint256::from(reserves[ask]).checked_sub(new_ask_reserve)?)?)
+    }
+
+    fn swap_in(&self, reserves: &[Uint128], offer: usize, ask: usize, ask_amount: Uint128) -> StdResult<Uint128> {
+        // offer_amount = offer_reserve * ask_amount / (ask_reserve - ask_amount), rounded up
+        let remaining = reserves[ask].checked_sub(ask_amount)?;
+        if remaining.is_zero() {
+            return Err(StdError::generic_err("Ask amount exceeds the pool reserve"));
+        }
+        let numerator = reserves[offer].full_mul(ask_amount);
+        let offer_amount = numerator.checked_add(Uint256::from(remaining).checked_sub(Uint256::one())?)?.checked_div(Uint256::from(remaining))?;
+        Ok(Uint128::try_from(offer_amount)?)
+    }
+
+    fn spot_price(&self, reserves: &[Uint128], offer: usize, ask: usize) -> StdResult<Decimal256> {
+        Decimal256::checked_from_ratio(reserves[ask], reserves[offer])
+            .map_err(|_| StdError::generic_err("Empty pool reserve"))
+    }
+
+    // Every asset holds the same share of the pool value
+    fn weights(&self, asset_count: usize) -> Vec<Decimal256> {
+        vec![Decimal256::from_ratio(1u128, asset_count as u128); asset_count]
+    }
+}
+
+/// Weighted product pool (x^w1 * y^w2 * ... = k)
+pub struct Weighted {
+    pub weights: Vec<Decimal>,
+}
+
+impl Weighted {
+    // w_a / w_b
+    fn weight_ratio(&self, a: usize, b: usize) -> StdResult<Decimal256> {
+        checked_div(Decimal256::from(self.weights[a]), Decimal256::from(self.weights[b]))
+    }
+}
+
+impl PoolCurve for Weighted {
+    fn swap_out(&self, reserves: &[Uint128], offer: usize, ask: usize, offer_amount: Uint128) -> StdResult<Uint128> {
+        // out = reserve_ask * (1 - (reserve_offer / (reserve_offer + offer_amount)) ^ (weight_offer / weight_ask))
+        let new_offer_reserve = reserves[offer].checked_add(offer_amount)?;
+        let base = Decimal256::checked_from_ratio(reserves[offer], new_offer_reserve)
+            .map_err(|_| StdError::generic_err("Empty pool reserve"))?;
+
+        let remaining_share = pow(base, self.weight_ratio(offer, ask)?)?;
+        let output_share = Decimal256::one().checked_sub(remaining_share)?;
+
+        Ok(Uint128::try_from(Uint256::from(reserves[ask]).mul_floor(output_share))?)
+    }
+
+    fn swap_in(&self, reserves: &[Uint128], offer: usize, ask: usize, ask_amount: Uint128) -> StdResult<Uint128> {
+        // in = reserve_offer * ((reserve_ask / (reserve_ask - ask_amount)) ^ (weight_ask / weight_offer) - 1), rounded up
+        let remaining = reserves[ask].checked_sub(ask_amount)?;
+        let base = Decimal256::checked_from_ratio(reserves[ask], remaining)
+            .map_err(|_| StdError::generic_err("Ask amount exceeds the pool reserve"))?;
+
+        let growth = pow(base, self.weight_ratio(ask, offer)?)?.checked_sub(Decimal256::one())?;
+
+        Ok(Uint128::try_from(Uint256::from(reserves[offer]).mul_ceil(growth))?)
+    }
+
+    fn spot_price(&self, reserves: &[Uint128], offer: usize, ask: usize) -> StdResult<Decimal256> {
+        // (reserve_ask / weight_ask) / (reserve_offer / weight_offer)
+        let ratio = Decimal256::checked_from_ratio(reserves[ask], reserves[offer])
+            .map_err(|_| StdError::generic_err("Empty pool reserve"))?;
+        Ok(ratio.checked_mul(self.weight_ratio(offer, ask)?)?)
+    }
+
+    fn weights(&self, _asset_count: usize) -> Vec<Decimal256> {
+        self.weights.iter().map(|weight| Decimal256::from(*weight)).collect()
+    }
+
+    /// The counter deposit carries value in the weight ratio at the current spot price:
+    /// value_counter / value_asset = weight_counter / weight_asset
+    fn counter_deposit(&self, reserves: &[Uint128], asset: usize, amount: Uint128, counter: usize) -> StdResult<Uint128> {
+        let value = Uint256::from(amount).mul_floor(self.spot_price(reserves, asset, counter)?);
+        Ok(Uint128::try_from(value.mul_floor(self.weight_ratio(counter, asset)?))?)
+    }
+}
+
+/// The curve of a reserve based pool. Concentrated liquidity pools price swaps through
+/// their tick ranges instead and have no curve.
+pub fn pool_curve(pool_type: &PoolType) -> Result<Box<dyn PoolCurve>, ContractError> {
+    match pool_type {
+        PoolType::ConstantProduct => Ok(Box::new(ConstantProduct)),
+        PoolType::Weighted { weights } => Ok(Box::new(Weighted { weights: weights.clone() })),
+        PoolType::Concentrated { .. } => Err(ContractError::InvalidPoolType {}),
+    }
+}
@@ -0,0 +1,96 @@
+use cosmwasm_std::{Decimal, Decimal256, Env, StdError, StdResult, Storage, Uint128};
+use crate::curve::PoolCurve;
+use crate::error::ContractError;
+use crate::math::checked_div;
+use crate::msg::{Config, DynamicFee};
+use crate::state::{PRICE_BUCKETS, PriceBucket};
+
+// Intervals the window is split into. Only the lowest and highest price of each interval are kept, so
+// however many swaps there are, the window can't be flushed before its prices get old
+const PRICE_BUCKETS_PER_WINDOW: u64 = 16;
+
+fn bucket_interval(dynamic_fee: &DynamicFee) -> u64 {
+    (dynamic_fee.window / PRICE_BUCKETS_PER_WINDOW).max(1)
+}
+
+// Price buckets that start within the window of the dynamic fee, oldest first
+fn recent_buckets(storage: &dyn Storage, env: &Env, dynamic_fee: &DynamicFee) -> StdResult<Vec<PriceBucket>> {
+    let window_start = env.block.time.seconds().saturating_sub(dynamic_fee.window);
+    let mut buckets = PRICE_BUCKETS.may_load(storage)?.unwrap_or_default();
+    buckets.retain(|bucket| bucket.start >= window_start);
+    Ok(buckets)
+}
+
+// Swap fee share of the pool at the current price. With a dynamic fee it moves from the min fee to the
+// max fee with the volatility of the swap prices in the window and the current price:
+// fee = min_fee + (max_fee - min_fee) * min(volatility / max_volatility, 1)
+// where volatility = (highest price - lowest price) / lowest price
+pub(crate) fn swap_fee_share(
+    storage: &dyn Storage,
+    env: &Env,
+    config: &Config,
+    curve: &dyn PoolCurve,
+    reserves: &[Uint128],
+) -> Result<Decimal, ContractError> {
+    let Some(dynamic_fee) = &config.dynamic_fee else {
+        return Ok(config.fee_share);
+    };
+
+    let current_price = curve.spot_price(reserves, 0, 1)?;
+    let (low, high) = recent_buckets(storage, env, dynamic_fee)?
+        .into_iter()
+        .fold((current_price, current_price), |(low, high), bucket| (low.min(bucket.low), high.max(bucket.high)));
+    if low.is_zero() {
+        return Ok(dynamic_fee.max_fee);
+    }
+
+    let volatility = checked_div(high.checked_sub(low)?, low)?;
+    let scale = checked_div(volatility, Decimal256::from(dynamic_fee.max_volatility))?.min(Decimal256::one());
+    let fee_range = Decimal256::from(dynamic_fee.max_fee.checked_sub(dynamic_fee.min_fee)?);
+    let fee_share = Decimal256::from(dynamic_fee.min_fee).checked_add(fee_range.checked_mul(scale)?)?;
+    // Between the min and max fee, so always below one
+    Ok(Decimal::try_from(fee_share).map_err(|err| StdError::generic_err(err.to_string()))?)
+}
+
+// Record the prices before and after a swap in the bucket of the current interval for the volatility
+// of the dynamic fee
+pub(crate) fn record_swap_prices(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    curve: &dyn PoolCurve,
+    reserves_before: &[Uint128],
+    reserves_after: &[Uint128],
+) -> Result<(), ContractError> {
+    let Some(dynamic_fee) = &config.dynamic_fee else {
+        return Ok(());
+    };
+
+    let interval = bucket_interval(dynamic_fee);
+    let start = env.block.time.seconds() / interval * interval;
+    let price_before = curve.spot_price(reserves_before, 0, 1)?;
+    let price_after = curve.spot_price(reserves_after, 0, 1)?;
+    let (low, high) = (price_before.min(price_after), price_before.max(price_after));
+
+    let mut buckets = recent_buckets(storage, env, dynamic_fee)?;
+    match buckets.last_mut() {
+        Some(bucket) if bucket.start == start => {
+            bucket.low = bucket.low.min(low);
+            bucket.high = bucket.high.max(high);
+        }
+        _ => buckets.push(PriceBucket { start, low, high }),
+    }
+    PRICE_BUCKETS.save(storage, &buckets)?;
+    Ok(())
+}
+
+// The fees are ordered and below one, and the window and max volatility are non-zero
+pub(crate) fn validate_dynamic_fee(dynamic_fee: &DynamicFee) -> Result<(), ContractError> {
+    if dynamic_fee.min_fee > dynamic_fee.max_fee
+        || dynamic_fee.max_fee >= Decimal::one()
+        || dynamic_fee.window == 0
+        || dynamic_fee.max_volatility.is_zero() {
+        return Err(ContractError::InvalidDynamicFee {});
+    }
+    Ok(())
+}
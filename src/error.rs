@@ -1,10 +1,10 @@
-use cosmwasm_std::{OverflowError, StdError};
+use cosmwasm_std::{DivideByZeroError, OverflowError, OverflowOperation, StdError};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum ContractError {
     #[error("{0}")]
-    Std(#[from] StdError),
+    Std(StdError),
 
     #[error("Unauthorized")]
     Unauthorized {},
@@ -27,12 +27,83 @@ pub enum ContractError {
     #[error("Deposit too small")]
     DepositTooSmall {},
 
+    #[error("Invalid pool weights: weights must be non-zero and add up to one")]
+    InvalidWeights {},
+
+    #[error("Operation not supported by this pool type")]
+    InvalidPoolType {},
+
+    #[error("Invalid tick range")]
+    InvalidTickRange {},
+
+    #[error("Insufficient liquidity")]
+    InsufficientLiquidity {},
+
+    #[error("Invalid ask asset")]
+    InvalidAskAsset {},
+
+    #[error("Flash loan already in progress")]
+    FlashLoanInProgress {},
+
+    #[error("Flash loan not repaid with the fee")]
+    FlashLoanNotRepaid {},
+
+    #[error("Unknown reply id")]
+    UnknownReplyId {},
+
+    #[error("Position NFTs are not enabled for this pool")]
+    PositionNftsDisabled {},
+
+    #[error("A position NFT is withdrawn for its whole LP amount")]
+    InvalidPositionAmount {},
+
+    #[error("Invalid dynamic fee: the fees must be ordered and below one, the window and max volatility non-zero, and the pool a single pair that isn't concentrated")]
+    InvalidDynamicFee {},
+
+    #[error("Invalid fee discount: a discount can't be above one")]
+    InvalidFeeDiscount {},
+
+    #[error("Invalid max referral commission: the commission has to be below one")]
+    InvalidMaxReferralCommission {},
+
+    #[error("Invalid referral: a referral commission needs a referral address and can't be above the max referral commission")]
+    InvalidReferral {},
+
+    #[error("Invalid migration: only older versions of this contract can be migrated")]
+    InvalidMigration {},
+
     #[error("Overflow Error")]
     Overflow(OverflowError),
+
+    #[error("Underflow Error")]
+    Underflow {},
+
+    #[error("Division by zero")]
+    DivideByZero {},
 }
 
 impl From<OverflowError> for ContractError {
     fn from(err: OverflowError) -> ContractError {
-        ContractError::Overflow(err)
+        match err.operation {
+            OverflowOperation::Sub => ContractError::Underflow {},
+            _ => ContractError::Overflow(err),
+        }
+    }
+}
+
+impl From<DivideByZeroError> for ContractError {
+    fn from(_: DivideByZeroError) -> ContractError {
+        ContractError::DivideByZero {}
+    }
+}
+
+// Arithmetic failures of the pool math come back as standard errors and get their own variants
+impl From<StdError> for ContractError {
+    fn from(err: StdError) -> ContractError {
+        match err {
+            StdError::Overflow { source, .. } => source.into(),
+            StdError::DivideByZero { .. } => ContractError::DivideByZero {},
+            err => ContractError::Std(err),
+        }
     }
 }
\ No newline at end of file
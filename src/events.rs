@@ -0,0 +1,72 @@
+use cosmwasm_std::{Addr, Coin, Event, Uint128};
+use crate::state::PoolReserves;
+
+// Events for indexers, emitted as wasm-swap, wasm-referral, wasm-deposit, wasm-withdraw, wasm-collect_fees and
+// wasm-reserves. Assets are listed as comma separated coins, e.g. 100asset1,200asset2, and the reserves are the
+// ones after the action
+
+// Comma separated coins, empty for no coins
+fn coins_attribute(coins: &[Coin]) -> String {
+    coins.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",")
+}
+
+fn reserves_attribute(pool_reserves: &PoolReserves) -> String {
+    coins_attribute(&pool_reserves.assets)
+}
+
+pub fn swap_event(sender: &Addr, receiver: &Addr, offer: &Coin, ask: &Coin, fee: Uint128, protocol_fee: Uint128, pool_reserves: &PoolReserves) -> Event {
+    Event::new("swap")
+        .add_attribute("sender", sender)
+        .add_attribute("receiver", receiver)
+        .add_attribute("offer_asset", &offer.denom)
+        .add_attribute("offer_amount", offer.amount)
+        .add_attribute("ask_asset", &ask.denom)
+        .add_attribute("ask_amount", ask.amount)
+        .add_attribute("fee", fee)
+        .add_attribute("protocol_fee", protocol_fee)
+        .add_attribute("reserves", reserves_attribute(pool_reserves))
+}
+
+// Commission of a swap sent to its referrer. The offer amount of the swap event doesn't include it
+pub fn referral_event(sender: &Addr, referral_address: &Addr, commission: &Coin) -> Event {
+    Event::new("referral")
+        .add_attribute("sender", sender)
+        .add_attribute("referral_address", referral_address)
+        .add_attribute("commission_asset", &commission.denom)
+        .add_attribute("commission_amount", commission.amount)
+}
+
+pub fn deposit_event(sender: &Addr, receiver: &Addr, assets: &[Coin], lp_amount: Uint128, pool_reserves: &PoolReserves) -> Event {
+    Event::new("deposit")
+        .add_attribute("sender", sender)
+        .add_attribute("receiver", receiver)
+        .add_attribute("assets", coins_attribute(assets))
+        .add_attribute("lp_amount", lp_amount)
+        .add_attribute("reserves", reserves_attribute(pool_reserves))
+}
+
+pub fn withdraw_event(sender: &Addr, receiver: &Addr, assets: &[Coin], lp_amount: Uint128, pool_reserves: &PoolReserves) -> Event {
+    Event::new("withdraw")
+        .add_attribute("sender", sender)
+        .add_attribute("receiver", receiver)
+        .add_attribute("assets", coins_attribute(assets))
+        .add_attribute("lp_amount", lp_amount)
+        .add_attribute("reserves", reserves_attribute(pool_reserves))
+}
+
+// Swap fees a concentrated liquidity position collected, they're held apart from the reserves
+pub fn collect_fees_event(sender: &Addr, receiver: &Addr, position_id: u64, fees: &[Coin], pool_reserves: &PoolReserves) -> Event {
+    Event::new("collect_fees")
+        .add_attribute("sender", sender)
+        .add_attribute("receiver", receiver)
+        .add_attribute("position_id", position_id.to_string())
+        .add_attribute("assets", coins_attribute(fees))
+        .add_attribute("reserves", reserves_attribute(pool_reserves))
+}
+
+// Reserves after an action that changes them outside of swaps and liquidity, i.e. sync, skim and flash loan repayment
+pub fn reserves_event(action: &str, pool_reserves: &PoolReserves) -> Event {
+    Event::new("reserves")
+        .add_attribute("action", action)
+        .add_attribute("reserves", reserves_attribute(pool_reserves))
+}
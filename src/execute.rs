@@ -1,39 +1,35 @@
-use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, DepsMut, Env, MessageInfo, Response, to_binary, to_json_binary, Uint128, WasmMsg};
+use cosmwasm_std::{Addr, BankMsg, Coin, CosmosMsg, Decimal, Decimal256, DepsMut, Env, MessageInfo, QuerierWrapper, Response, StdError, StdResult, to_json_binary, Uint128, Uint256, WasmMsg};
 use cw20::Cw20ExecuteMsg;
+use crate::concentrated;
 use crate::error::ContractError;
-use crate::msg::Config;
-use crate::state::{CONFIG, POOL_RESERVES, SHARE_BALANCES, TOTAL_SUPPLY};
+use crate::curve::{pool_curve, PoolCurve};
+use crate::msg::{Config, PoolType};
+use crate::dynamic_fee::{record_swap_prices, swap_fee_share};
+use crate::events::{deposit_event, reserves_event, swap_event, withdraw_event};
+use crate::fee_discount::discounted_fee_share;
+use crate::cost_basis::{record_deposit, record_withdraw};
+use crate::position_nft::{burn_position_nft, mint_position_nft};
+use crate::query::query_lp_balance;
+use crate::referral::{add_referral, take_referral_commission};
+use crate::state::{CONFIG, FLASH_LOAN_FEES, POOL_RESERVES, PoolReserves, TOTAL_SUPPLY};
+use crate::stats::{count_deposit, count_withdraw, record_swap};
 
 pub fn deposit(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    asset1: Coin,
-    asset2: Coin,
+    assets: Vec<Coin>,
 ) -> Result<Response, ContractError> {
-    // Validate that exactly two assets are sent
-    if info.funds.len() != 2 {
-        return Err(ContractError::InvalidFunds {});
-    }
-
-    // Validate that the correct assets and amounts are sent
-    let mut asset1_received = false;
-    let mut asset2_received = false;
-
-    for fund in &info.funds {
-        if fund.denom == asset1.denom && fund.amount == asset1.amount {
-            asset1_received = true;
-        } else if fund.denom == asset2.denom && fund.amount == asset2.amount {
-            asset2_received = true;
-        }
-    }
+    // Load current pool state from storage
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
 
-    if !asset1_received || !asset2_received {
+    // Validate that exactly the deposited assets are sent
+    if info.funds.len() != assets.len() || assets.iter().any(|asset| !info.funds.contains(asset)) {
         return Err(ContractError::InvalidFunds {});
     }
 
-    // Load current pool state from storage
-    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    // Validate the deposited assets, lined up with the pool assets
+    let deposits = deposit_amounts(&pool_reserves, &assets)?;
 
     // Load the contract config
     let config = CONFIG.load(deps.storage)?;
@@ -41,65 +37,150 @@ pub fn deposit(
     // Load total supply of liquidity tokens
     let mut total_supply = TOTAL_SUPPLY.load(deps.storage)?;
 
-    // Calculate the ratio of the deposit: (deposit_x / deposit_y) = (reserve_x/reserve_y)
-    let expected_deposit2 = (asset1.amount * pool_reserves.asset2.amount) / pool_reserves.asset1.amount;
-    let expected_deposit1 = (asset2.amount * pool_reserves.asset1.amount) / pool_reserves.asset2.amount;
+    // Concentrated liquidity pools have no curve, their liquidity is provided through positions
+    let curve = pool_curve(&config.pool_type)?;
+    let reserves = pool_reserves.amounts();
+
+    check_liquidity(&reserves, total_supply)?;
+    let ideal_deposits = ideal_deposits(curve.as_ref(), &reserves, &deposits)?;
+    let lp_tokens_to_mint = deposit_lp_amount(curve.as_ref(), &config, &reserves, &deposits, total_supply)?;
+    validate_deposit(&config, &deposits, &ideal_deposits, lp_tokens_to_mint)?;
+
+    // Price before the deposit, recorded in the cost basis of the depositor
+    let entry_price = curve.spot_price(&reserves, 0, 1)?;
+    record_deposit(deps.storage, &info.sender, &pool_reserves, &deposits, lp_tokens_to_mint, entry_price)?;
+
+    // Mint LP tokens to the depositor's address, or a position NFT recording the deposit
+    let deposited = withdrawn_coins(&pool_reserves, deposits.clone());
+    let mut position_id = None;
+    let mint_msg = if config.position_nft_addr.is_some() {
+        let (token_id, mint_msg) = mint_position_nft(deps.storage, &env, &config, &info.sender, lp_tokens_to_mint, deposited.clone(), entry_price)?;
+        position_id = Some(token_id);
+        mint_msg
+    } else {
+        mint_liquidity_tokens(&config, info.sender.clone(), lp_tokens_to_mint)?
+    };
 
-    // Calculate the minimum and maximum expected amounts for the assets based on a specified tolerance
-    let min_expected_deposit2 = expected_deposit2 * (Decimal::one() - config.tolerance_percentage);
-    let max_expected_deposit2 = expected_deposit2 * (Decimal::one() + config.tolerance_percentage);
-    let min_expected_deposit1 = expected_deposit1 * (Decimal::one() - config.tolerance_percentage);
-    let max_expected_deposit1 = expected_deposit1 * (Decimal::one() + config.tolerance_percentage);
+    // Update pool reserves in storage
+    for (reserve, amount) in pool_reserves.assets.iter_mut().zip(&deposits) {
+        reserve.amount = reserve.amount.checked_add(*amount)?;
+    }
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
 
-    // Check if the deposited amount for the assets is within the tolerance range
-    if asset1.amount < min_expected_deposit1 || asset1.amount > max_expected_deposit1 {
-        return Err(ContractError::InvalidDepositRatio {});
+    // Update total LP supply in storage
+    total_supply = total_supply.checked_add(lp_tokens_to_mint)?;
+    TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
+    count_deposit(deps.storage, pool_reserves.assets.len())?;
+
+    let mut response = Response::new()
+        .add_message(mint_msg)
+        .add_event(deposit_event(&info.sender, &info.sender, &deposited, lp_tokens_to_mint, &pool_reserves))
+        .add_attribute("action", "deposit")
+        .add_attribute("sender", info.sender.to_string())
+        .add_attribute("LP_tokens_minted", lp_tokens_to_mint.to_string());
+    if let Some(position_id) = position_id {
+        response = response.add_attribute("position_id", position_id);
     }
-    if asset2.amount < min_expected_deposit2 || asset2.amount > max_expected_deposit2 {
-        return Err(ContractError::InvalidDepositRatio {});
+    Ok(response)
+}
+
+// Validate a deposit of some of the pool assets and return the deposited amount of every
+// pool asset, in pool asset order
+pub(crate) fn deposit_amounts(
+    pool_reserves: &PoolReserves,
+    assets: &[Coin],
+) -> Result<Vec<Uint128>, ContractError> {
+    if assets.is_empty() {
+        return Err(ContractError::InvalidFunds {});
+    }
+
+    let mut deposits = vec![Uint128::zero(); pool_reserves.assets.len()];
+    for asset in assets {
+        if asset.amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+
+        // Each asset has to be a pool asset that is deposited once
+        let index = pool_reserves.index_of(&asset.denom).ok_or(ContractError::InvalidFunds {})?;
+        if !deposits[index].is_zero() {
+            return Err(ContractError::InvalidFunds {});
+        }
+        deposits[index] = asset.amount;
+    }
+    Ok(deposits)
+}
+
+// Deposits and withdrawals are priced against the reserves and the LP supply, which can't be empty
+pub(crate) fn check_liquidity(reserves: &[Uint128], total_supply: Uint128) -> Result<(), ContractError> {
+    if total_supply.is_zero() || reserves.iter().any(|reserve| reserve.is_zero()) {
+        return Err(ContractError::InsufficientLiquidity {});
     }
+    Ok(())
+}
 
-    // Calculate the amount of liquidity tokens to mint: liquidity_tokens = (total_supply_of_liquidity_tokens * max(deposit_x / reserve_x, deposit_y / reserve_y))
-    // Note: The above formula is broken down into several separate calculations below address potential overflow errors with rust
+// Deposit of each asset that keeps the pool price unchanged given the deposit of the next asset.
+// Only a deposit of every asset has to match the pool price, otherwise there are no ideal deposits
+pub(crate) fn ideal_deposits(
+    curve: &dyn PoolCurve,
+    reserves: &[Uint128],
+    deposits: &[Uint128],
+) -> StdResult<Vec<Uint128>> {
+    if deposits.iter().any(|amount| amount.is_zero()) {
+        return Ok(vec![]);
+    }
 
-    // Calculate the liquidity tokens to mint using the smallest proportional deposit
-    // To avoid integer division rounding down to zero, we use checked multiplication and division
-    let asset1_ratio = asset1.amount.checked_mul(total_supply)?;
-    let asset2_ratio = asset2.amount.checked_mul(total_supply)?;
+    (0..deposits.len())
+        .map(|asset| {
+            let next = (asset + 1) % deposits.len();
+            curve.counter_deposit(reserves, next, deposits[next], asset)
+        })
+        .collect()
+}
 
-    let lp_tokens_to_mint_asset1 = asset1_ratio / pool_reserves.asset1.amount;
-    let lp_tokens_to_mint_asset2 = asset2_ratio / pool_reserves.asset2.amount;
+// LP tokens minted for a deposit. A deposit of every asset gets its smallest proportional share,
+// a deposit of some of the assets is priced against the invariant and pays the swap fee on the unbalanced part
+pub(crate) fn deposit_lp_amount(
+    curve: &dyn PoolCurve,
+    config: &Config,
+    reserves: &[Uint128],
+    deposits: &[Uint128],
+    total_supply: Uint128,
+) -> StdResult<Uint128> {
+    if deposits.iter().all(|amount| !amount.is_zero()) {
+        curve.lp_for_deposit(reserves, deposits, total_supply)
+    } else {
+        curve.lp_for_unbalanced_deposit(reserves, deposits, total_supply, config.fee_share)
+    }
+}
 
-    // Use the minimum of the two calculated mint amounts to ensure proportional addition of liquidity
-    let lp_tokens_to_mint = std::cmp::min(lp_tokens_to_mint_asset1, lp_tokens_to_mint_asset2);
+// Check the deposit of each asset against its ideal deposit and that the deposit mints LP tokens
+pub(crate) fn validate_deposit(
+    config: &Config,
+    deposits: &[Uint128],
+    ideal_deposits: &[Uint128],
+    lp_tokens_to_mint: Uint128,
+) -> Result<(), ContractError> {
+    for (deposit, expected_deposit) in deposits.iter().zip(ideal_deposits) {
+        // Calculate the minimum and maximum expected amounts for the asset based on a specified tolerance,
+        // in 256 bits as the maximum can go past the largest reserve
+        let tolerance = Decimal256::from(config.tolerance_percentage);
+        let expected_deposit = Uint256::from(*expected_deposit);
+        let min_expected_deposit = expected_deposit.mul_floor(Decimal256::one().saturating_sub(tolerance));
+        let max_expected_deposit = expected_deposit.mul_ceil(Decimal256::one().checked_add(tolerance)?);
+
+        // Check if the deposited amount for the asset is within the tolerance range
+        let deposit = Uint256::from(*deposit);
+        if deposit < min_expected_deposit || deposit > max_expected_deposit {
+            return Err(ContractError::InvalidDepositRatio {});
+        }
+    }
 
     // Check if the resulting LP tokens to mint is zero
     if lp_tokens_to_mint.is_zero() {
         // The deposit amounts are too small relative to the pool size
         return Err(ContractError::DepositTooSmall {});
     }
-
-    // Mint LP tokens to the depositor's address
-    mint_liquidity_tokens(&config, info.sender.clone(), lp_tokens_to_mint)?;
-
-    // Update pool reserves in storage
-    pool_reserves.asset1.amount += asset1.amount;
-    pool_reserves.asset2.amount += asset2.amount;
-    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
-
-    // Update total LP supply in storage
-    total_supply += lp_tokens_to_mint;
-    TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
-
-    // Add depositor's LP share to storage
-    let mut depositors_shares = SHARE_BALANCES.may_load(deps.storage, &info.sender)?.unwrap_or(Uint128::zero());
-    depositors_shares += lp_tokens_to_mint;
-    SHARE_BALANCES.save(deps.storage, &info.sender, &depositors_shares)?;
-
-    Ok(Response::new()
-        .add_attribute("action", "deposit")
-        .add_attribute("sender", info.sender.to_string())
-        .add_attribute("LP_tokens_minted", lp_tokens_to_mint.to_string()))
+    Ok(())
 }
 
 pub fn withdraw(
@@ -107,14 +188,35 @@ pub fn withdraw(
     _env: Env,
     info: MessageInfo,
     amount_to_burn: Uint128,
+    asset: Option<String>,
+    position_id: Option<String>,
 ) -> Result<Response, ContractError> {
-    // Load user's share amount
-    let user_shares = SHARE_BALANCES.load(deps.storage, &info.sender)?;
+    // Load the contract config
+    let config = CONFIG.load(deps.storage)?;
 
-    // Validate that the user hold enough shares to burn the requested amount
-    if amount_to_burn > user_shares {
-        return Err(ContractError::WithdrawError {});
-    }
+    let burn_msg = match position_id {
+        // Burn the whole position NFT, which needs an approval for the pool
+        Some(token_id) => burn_position_nft(&deps.querier, deps.storage, &config, &info.sender, &token_id, amount_to_burn)?,
+        None => {
+            // Load user's share amount from the LP token
+            let user_shares = query_lp_balance(deps.as_ref(), &config, &info.sender)?;
+
+            // Validate that the user hold enough shares to burn the requested amount
+            if amount_to_burn > user_shares {
+                return Err(ContractError::WithdrawError {});
+            }
+
+            // Burn the LP tokens from the user's balance, which needs an allowance for the pool
+            let burn_msg = Cw20ExecuteMsg::BurnFrom { owner: info.sender.to_string(), amount: amount_to_burn };
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.lp_token_addr.to_string(),
+                msg: to_json_binary(&burn_msg)?,
+                funds: vec![],
+            })
+        }
+    };
+
+    record_withdraw(deps.storage, &info.sender, amount_to_burn)?;
 
     // Load total supply of liquidity tokens
     let mut total_supply = TOTAL_SUPPLY.load(deps.storage)?;
@@ -122,122 +224,269 @@ pub fn withdraw(
     // Load current pool state from storage
     let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
 
-    // Calculate the proportion of the total supply that the LP tokens represent
-    let lp_token_share = Decimal::from_ratio(amount_to_burn, total_supply);
-
     // Calculate the amount of each asset to return to the LP
-    let amount1 = lp_token_share * pool_reserves.asset1.amount;
-    let amount2 = lp_token_share * pool_reserves.asset2.amount;
+    let amounts = withdraw_amounts(&config, &pool_reserves, total_supply, amount_to_burn, asset)?;
 
     // Update pool reserves in storage
-    pool_reserves.asset1.amount -= amount1;
-    pool_reserves.asset2.amount -= amount2;
+    for (reserve, amount) in pool_reserves.assets.iter_mut().zip(&amounts) {
+        reserve.amount = reserve.amount.checked_sub(*amount)?;
+    }
     POOL_RESERVES.save(deps.storage, &pool_reserves)?;
 
     // Update total LP supply in storage
-    total_supply -= amount_to_burn;
+    total_supply = total_supply.checked_sub(amount_to_burn)?;
     TOTAL_SUPPLY.save(deps.storage, &total_supply)?;
+    count_withdraw(deps.storage, pool_reserves.assets.len())?;
 
-    // Load the contract config
-    let config = CONFIG.load(deps.storage)?;
+    // Send the withdrawn assets to the user
+    let withdrawn = withdrawn_coins(&pool_reserves, amounts);
+    let withdrawn_attribute = withdrawn.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",");
+
+    let mut response = Response::new()
+        .add_message(burn_msg)
+        .add_event(withdraw_event(&info.sender, &info.sender, &withdrawn, amount_to_burn, &pool_reserves));
+    if !withdrawn.is_empty() {
+        response = response.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: withdrawn,
+        });
+    }
 
-    // Burn the LP tokens from the user's balance
-    let burn_msg = Cw20ExecuteMsg::Burn { amount: amount_to_burn };
-    let exec_burn_msg = CosmosMsg::Wasm(WasmMsg::Execute {
-        contract_addr: config.lp_token_addr.to_string(),
-        msg: to_json_binary(&burn_msg)?,
-        funds: vec![],
-    });
+    Ok(response
+        .add_attribute("action", "withdraw_liquidity")
+        .add_attribute("withdrawn_assets", withdrawn_attribute))
+}
 
-    // Send the withdrawn assets to the user
-    let send_x_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![Coin { denom: pool_reserves.asset1.denom.to_string(), amount: amount1 }],
-    };
-    let send_y_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![Coin { denom: pool_reserves.asset2.denom.to_string(), amount: amount2 }],
+// Amount of each asset paid out for burning LP tokens, in pool asset order. Proportional to
+// the reserves, or all in the requested asset
+pub(crate) fn withdraw_amounts(
+    config: &Config,
+    pool_reserves: &PoolReserves,
+    total_supply: Uint128,
+    amount_to_burn: Uint128,
+    asset: Option<String>,
+) -> Result<Vec<Uint128>, ContractError> {
+    // Concentrated liquidity pools have no curve, their liquidity is withdrawn through positions
+    let curve = pool_curve(&config.pool_type)?;
+    let reserves = pool_reserves.amounts();
+    check_liquidity(&reserves, total_supply)?;
+
+    let amounts = match asset {
+        None => curve.assets_for_withdraw(&reserves, amount_to_burn, total_supply)?,
+        Some(denom) => {
+            let index = pool_reserves.index_of(&denom).ok_or(ContractError::InvalidAskAsset {})?;
+            let mut amounts = vec![Uint128::zero(); reserves.len()];
+            amounts[index] = curve.asset_for_single_withdraw(&reserves, index, amount_to_burn, total_supply, config.fee_share)?;
+            amounts
+        }
     };
+    Ok(amounts)
+}
 
-    Ok(Response::new()
-        .add_message(exec_burn_msg)
-        .add_message(send_x_msg)
-        .add_message(send_y_msg)
-        .add_attribute("action", "withdraw_liquidity")
-        .add_attribute("withdrawn_asset_x", amount1.to_string())
-        .add_attribute("withdrawn_asset_y", amount2.to_string()))
+// The non-zero amounts of the pool assets as coins, e.g. the withdrawn or deposited amounts
+pub(crate) fn withdrawn_coins(pool_reserves: &PoolReserves, amounts: Vec<Uint128>) -> Vec<Coin> {
+    pool_reserves.assets
+        .iter()
+        .zip(amounts)
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(reserve, amount)| Coin { denom: reserve.denom.clone(), amount })
+        .collect()
 }
 
-// This swap function follows the constant product formula for an AMM (xy=K)
+// This swap function follows the constant product formula for an AMM (xy=K),
+// or the weighted product formula (x^w1 * y^w2 = k) for weighted pools.
+// Concentrated liquidity pools are handled in the concentrated module
 pub fn swap(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     offered_asset: Coin,
+    ask_denom: Option<String>,
+    referral_address: Option<String>,
+    referral_commission: Option<Decimal>,
 ) -> Result<Response, ContractError> {
 
+    // The offered asset must be sent along with the message, and nothing else
+    if info.funds != [offered_asset.clone()] {
+        return Err(ContractError::InvalidFunds {});
+    }
+
     // Load current pool state from storage
     let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
 
     // Load config
     let config = CONFIG.load(deps.storage)?;
 
-    // Calculate the invariant k before the swap
-    let k = pool_reserves.asset1.amount * pool_reserves.asset2.amount;
-
-    let swap_fee = offered_asset.amount * config.fee_share;
+    let (offer, ask) = swap_assets(&pool_reserves, &offered_asset.denom, ask_denom)?;
 
-    // Subtract fee from offered amount
-    let new_coin_amount = offered_asset.amount - swap_fee;
+    // The referral commission is taken from the offered asset before the swap
+    let (offered_asset, referral) = take_referral_commission(deps.api, &config, offered_asset, referral_address, referral_commission)?;
+    let sender = info.sender.clone();
 
-    let output_amount: Uint128;
-    let denom: String;
+    // Concentrated liquidity pools swap through their tick ranges
+    if let PoolType::Concentrated { .. } = config.pool_type {
+        let response = concentrated::swap(deps, env, info, &config, offered_asset)?;
+        return Ok(add_referral(response, &sender, referral));
+    }
 
-    if offered_asset.denom == pool_reserves.asset1.denom {
-        pool_reserves.asset1.amount += new_coin_amount;
+    let curve = pool_curve(&config.pool_type)?;
+    let reserves_before = pool_reserves.amounts();
+    let fee_share = swap_fee_share(deps.storage, &env, &config, curve.as_ref(), &reserves_before)?;
+    let fee_share = discounted_fee_share(deps.storage, &info.sender, fee_share)?;
+    let (output_amount, fee) = swap_amounts(&config, &reserves_before, offer, ask, offered_asset.amount, fee_share)?;
 
-        // Calculate new_reserve2 such that new_reserve1 * new_reserve2 = k
-        let new_reserve2 = Uint128::from(k/pool_reserves.asset1.amount);
+    // Update pool reserves, the fee stays in the reserves and accrues to the LPs
+    pool_reserves.assets[offer].amount = pool_reserves.assets[offer].amount.checked_add(offered_asset.amount)?;
+    pool_reserves.assets[ask].amount = pool_reserves.assets[ask].amount.checked_sub(output_amount)?;
 
-        output_amount = pool_reserves.asset2.amount - new_reserve2;
+    // Create the message to send the ask asset to the user
+    let ask_asset = Coin { denom: pool_reserves.assets[ask].denom.clone(), amount: output_amount };
+    let send_msg = BankMsg::Send {
+        to_address: info.sender.to_string(),
+        amount: vec![ask_asset.clone()],
+    };
 
-        // Update pool for asset2
-        pool_reserves.asset2.amount = new_reserve2;
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
+    record_swap_prices(deps.storage, &env, &config, curve.as_ref(), &reserves_before, &pool_reserves.amounts())?;
+    record_swap(deps.storage, &env, pool_reserves.assets.len(), offer, offered_asset.amount, fee)?;
+
+    // The pool takes no protocol fee, the whole fee goes to the LPs
+    let response = Response::new()
+        .add_message(CosmosMsg::Bank(send_msg))
+        .add_event(swap_event(&info.sender, &info.sender, &offered_asset, &ask_asset, fee, Uint128::zero(), &pool_reserves))
+        .add_attribute("action", "swap");
+    Ok(add_referral(response, &sender, referral))
+}
 
-        denom = pool_reserves.asset2.denom.clone();
+// Find the offered and asked assets, the ask asset is implied in a two asset pool
+pub(crate) fn swap_assets(
+    pool_reserves: &PoolReserves,
+    offer_denom: &str,
+    ask_denom: Option<String>,
+) -> Result<(usize, usize), ContractError> {
+    let offer = pool_reserves.index_of(offer_denom).ok_or(ContractError::InvalidFunds {})?;
+    let ask = match ask_denom {
+        Some(denom) => pool_reserves.index_of(&denom).filter(|ask| *ask != offer),
+        None if pool_reserves.assets.len() == 2 => Some(1 - offer),
+        None => None,
+    }
+        .ok_or(ContractError::InvalidAskAsset {})?;
+    Ok((offer, ask))
+}
 
-    } else if offered_asset.denom == pool_reserves.asset2.denom {
-        pool_reserves.asset2.amount += new_coin_amount;
+// Amount of the ask asset paid out and the fee kept by the pool for the offered amount, charging `fee_share`
+pub(crate) fn swap_amounts(
+    config: &Config,
+    reserves: &[Uint128],
+    offer: usize,
+    ask: usize,
+    offer_amount: Uint128,
+    fee_share: Decimal,
+) -> Result<(Uint128, Uint128), ContractError> {
+    let curve = pool_curve(&config.pool_type)?;
+    if reserves[offer].is_zero() || reserves[ask].is_zero() {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
 
-        // Calculate new_reserve2 such that new_reserve1 * new_reserve2 = k
-        let new_reserve1 = Uint128::from(k/pool_reserves.asset2.amount);
+    // The fee rounds up in favor of the pool
+    let swap_fee = Uint128::try_from(Uint256::from(offer_amount).mul_ceil(Decimal256::from(fee_share))).map_err(StdError::from)?;
 
-        output_amount = pool_reserves.asset1.amount - new_reserve1;
+    // Subtract fee from offered amount
+    let new_coin_amount = offer_amount.checked_sub(swap_fee)?;
 
-        // Update pool reserve in storage
-        pool_reserves.asset1.amount = new_reserve1;
+    // The ask reserve can't be emptied
+    let output_amount = curve.swap_out(reserves, offer, ask, new_coin_amount)?;
+    if output_amount >= reserves[ask] {
+        return Err(ContractError::InsufficientLiquidity {});
+    }
+    Ok((output_amount, swap_fee))
+}
 
-        denom = pool_reserves.asset1.denom.clone();
+// Bank balances of the pool contract in pool asset order
+pub(crate) fn bank_balances(querier: &QuerierWrapper, env: &Env, pool_reserves: &PoolReserves) -> StdResult<Vec<Uint128>> {
+    pool_reserves.assets
+        .iter()
+        .map(|asset| Ok(querier.query_balance(&env.contract.address, &asset.denom)?.amount))
+        .collect()
+}
 
-    } else {
+// Sync and skim only apply to reserve based pools outside of a flash loan. Concentrated liquidity
+// pools hold uncollected position fees above their reserves, and a flash loan is checked against the reserves
+fn check_reconcile(deps: &DepsMut, info: &MessageInfo) -> Result<(), ContractError> {
+    if !info.funds.is_empty() {
         return Err(ContractError::InvalidFunds {});
     }
+    if FLASH_LOAN_FEES.exists(deps.storage) {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+    if let PoolType::Concentrated { .. } = CONFIG.load(deps.storage)?.pool_type {
+        return Err(ContractError::InvalidPoolType {});
+    }
+    Ok(())
+}
 
-    // Create the message to send token B to the user
-    let send_msg = BankMsg::Send {
-        to_address: info.sender.to_string(),
-        amount: vec![Coin { denom, amount: output_amount }],
-    };
+pub fn sync(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    check_reconcile(&deps, &info)?;
 
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let balances = bank_balances(&deps.querier, &env, &pool_reserves)?;
+    for (reserve, balance) in pool_reserves.assets.iter_mut().zip(balances) {
+        reserve.amount = balance;
+    }
     POOL_RESERVES.save(deps.storage, &pool_reserves)?;
 
-    Ok(Response::new().add_message(CosmosMsg::Bank(send_msg)).add_attribute("action", "swap"))
+    let reserves_attribute = pool_reserves.assets.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",");
+    Ok(Response::new()
+        .add_event(reserves_event("sync", &pool_reserves))
+        .add_attribute("action", "sync")
+        .add_attribute("reserves", reserves_attribute))
 }
+
+pub fn skim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    to: String,
+) -> Result<Response, ContractError> {
+    check_reconcile(&deps, &info)?;
+    let recipient = deps.api.addr_validate(&to)?;
+
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let balances = bank_balances(&deps.querier, &env, &pool_reserves)?;
+    let excess: Vec<Coin> = pool_reserves.assets
+        .iter()
+        .zip(balances)
+        .map(|(reserve, balance)| Coin { denom: reserve.denom.clone(), amount: balance.saturating_sub(reserve.amount) })
+        .filter(|coin| !coin.amount.is_zero())
+        .collect();
+    let excess_attribute = excess.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",");
+
+    let mut response = Response::new()
+        .add_event(reserves_event("skim", &pool_reserves))
+        .add_attribute("action", "skim")
+        .add_attribute("to", &recipient);
+    // Nothing to skim when the balances match the reserves
+    if !excess.is_empty() {
+        response = response
+            .add_message(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: excess,
+            })
+            .add_attribute("skimmed_assets", excess_attribute);
+    }
+
+    Ok(response)
+}
+
 pub fn mint_liquidity_tokens(
     config: &Config,
     recipient: Addr,
     amount: Uint128,
-) -> Result<Response, ContractError> {
+) -> Result<CosmosMsg, ContractError> {
     let lp_token = config.lp_token_addr.clone();
 
     // Create the execution message for minting liquidity tokens
@@ -246,14 +495,9 @@ pub fn mint_liquidity_tokens(
         amount,
     };
 
-    let exec_msg = CosmosMsg::Wasm(WasmMsg::Execute {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
         contract_addr: lp_token,
-        msg: to_binary(mint_msg)?,
+        msg: to_json_binary(mint_msg)?,
         funds: vec![],
-    });
-
-    Ok(Response::new()
-        .add_message(exec_msg)
-        .add_attribute("action", "mint_liquidity_tokens")
-        .add_attribute("amount", amount.to_string()))
-}
\ No newline at end of file
+    }))
+}
@@ -0,0 +1,38 @@
+use cosmwasm_std::{Addr, Decimal, DepsMut, MessageInfo, Response, Storage};
+use crate::error::ContractError;
+use crate::owner::assert_owner;
+use crate::state::FEE_DISCOUNTS;
+
+// Set or remove the fee discount of an address, owner only. A discount is a share of the swap fee
+// between zero and one, so the fee never goes below zero
+pub fn set_fee_discount(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    discount: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    assert_owner(deps.storage, &info.sender)?;
+
+    let address = deps.api.addr_validate(&address)?;
+    match discount {
+        Some(discount) if discount > Decimal::one() => return Err(ContractError::InvalidFeeDiscount {}),
+        Some(discount) => FEE_DISCOUNTS.save(deps.storage, &address, &discount)?,
+        None => FEE_DISCOUNTS.remove(deps.storage, &address),
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "set_fee_discount")
+        .add_attribute("address", address)
+        .add_attribute("discount", discount.unwrap_or_default().to_string()))
+}
+
+// Fee discount of an address, zero without one
+pub(crate) fn fee_discount(storage: &dyn Storage, address: &Addr) -> Result<Decimal, ContractError> {
+    Ok(FEE_DISCOUNTS.may_load(storage, address)?.unwrap_or_default())
+}
+
+// The swap fee share charged to an address: fee_share * (1 - discount)
+pub(crate) fn discounted_fee_share(storage: &dyn Storage, address: &Addr, fee_share: Decimal) -> Result<Decimal, ContractError> {
+    let discount = fee_discount(storage, address)?;
+    Ok(fee_share.checked_mul(Decimal::one().checked_sub(discount)?)?)
+}
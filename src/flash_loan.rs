@@ -0,0 +1,85 @@
+use cosmwasm_std::{BankMsg, Binary, Coin, DepsMut, Env, MessageInfo, Response, SubMsg, Uint128, WasmMsg};
+use crate::error::ContractError;
+use crate::events::reserves_event;
+use crate::msg::PoolType;
+use crate::state::{CONFIG, FLASH_LOAN_FEES, POOL_RESERVES};
+
+pub const FLASH_LOAN_REPLY_ID: u64 = 1;
+
+pub fn flash_loan(
+    deps: DepsMut,
+    info: MessageInfo,
+    assets: Vec<Coin>,
+    callback_msg: Binary,
+) -> Result<Response, ContractError> {
+    // The callback can't take out another loan before repaying this one
+    if FLASH_LOAN_FEES.exists(deps.storage) {
+        return Err(ContractError::FlashLoanInProgress {});
+    }
+
+    // Concentrated liquidity pools pay fees to positions, not to the reserves
+    let config = CONFIG.load(deps.storage)?;
+    if let PoolType::Concentrated { .. } = config.pool_type {
+        return Err(ContractError::InvalidPoolType {});
+    }
+
+    if assets.is_empty() || !info.funds.is_empty() {
+        return Err(ContractError::InvalidFunds {});
+    }
+
+    // Each borrowed asset is a pool asset, borrowed once and at most up to its reserve
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let mut fees = vec![Uint128::zero(); pool_reserves.assets.len()];
+    let mut borrowed = vec![false; pool_reserves.assets.len()];
+    for asset in &assets {
+        if asset.amount.is_zero() {
+            return Err(ContractError::InvalidZeroAmount {});
+        }
+        let index = pool_reserves.index_of(&asset.denom).ok_or(ContractError::InvalidFunds {})?;
+        if borrowed[index] {
+            return Err(ContractError::InvalidFunds {});
+        }
+        if asset.amount > pool_reserves.assets[index].amount {
+            return Err(ContractError::InsufficientLiquidity {});
+        }
+        borrowed[index] = true;
+        fees[index] = asset.amount.mul_ceil(config.fee_share);
+    }
+    FLASH_LOAN_FEES.save(deps.storage, &fees)?;
+
+    let callback = WasmMsg::Execute {
+        contract_addr: info.sender.to_string(),
+        msg: callback_msg,
+        funds: vec![],
+    };
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: assets,
+        })
+        .add_submessage(SubMsg::reply_on_success(callback, FLASH_LOAN_REPLY_ID))
+        .add_attribute("action", "flash_loan")
+        .add_attribute("borrower", info.sender))
+}
+
+// Check that the borrower's callback paid back the loan and the fee, which then goes to the reserves
+pub fn flash_loan_reply(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
+    let fees = FLASH_LOAN_FEES.load(deps.storage)?;
+    FLASH_LOAN_FEES.remove(deps.storage);
+
+    let mut pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    for (reserve, fee) in pool_reserves.assets.iter_mut().zip(fees) {
+        let balance = deps.querier.query_balance(&env.contract.address, &reserve.denom)?;
+        let owed = reserve.amount.checked_add(fee)?;
+        if balance.amount < owed {
+            return Err(ContractError::FlashLoanNotRepaid {});
+        }
+        reserve.amount = owed;
+    }
+    POOL_RESERVES.save(deps.storage, &pool_reserves)?;
+
+    Ok(Response::new()
+        .add_event(reserves_event("flash_loan_repaid", &pool_reserves))
+        .add_attribute("action", "flash_loan_repaid"))
+}
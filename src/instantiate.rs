@@ -1,7 +1,13 @@
-use cosmwasm_std::{DepsMut, Env, MessageInfo, Response};
+use cosmwasm_std::{Decimal, DepsMut, Env, MessageInfo, Response};
+use crate::concentrated::initialize_pool;
+use crate::dynamic_fee::validate_dynamic_fee;
 use crate::error::ContractError;
-use crate::msg::{Config, InstantiateMsg};
-use crate::state::{CONFIG, POOL_RESERVES, PoolReserves, TOTAL_SUPPLY};
+use crate::msg::{Config, InstantiateMsg, PoolType};
+use crate::state::{CONFIG, OWNER, POOL_RESERVES, PoolReserves, TOTAL_SUPPLY};
+
+// Number of assets a pool can hold
+const MIN_POOL_ASSETS: usize = 2;
+const MAX_POOL_ASSETS: usize = 8;
 
 pub fn set_up_contract(
     deps: DepsMut,
@@ -11,17 +17,58 @@ pub fn set_up_contract(
 ) -> Result<Response, ContractError> {
     // Validate LP token address and save to config
     let lp_token_address = deps.api.addr_validate(&msg.contract_config.lp_token_addr)?;
+    let position_nft_address = msg.contract_config.position_nft_addr
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    // Validate the pool assets: between 2 and 8 distinct, funded assets
+    let asset_count = msg.initial_funding.len();
+    if !(MIN_POOL_ASSETS..=MAX_POOL_ASSETS).contains(&asset_count)
+        || msg.initial_funding.iter().any(|coin| coin.amount.is_zero())
+        || msg.initial_funding.iter().enumerate().any(|(i, coin)| msg.initial_funding[..i].iter().any(|other| other.denom == coin.denom)) {
+        return Err(ContractError::InvalidInitialFunds {});
+    }
+
+    match &msg.contract_config.pool_type {
+        // Weighted pools need a non-zero weight per asset and the weights make up the whole pool value
+        PoolType::Weighted { weights } if weights.len() != asset_count
+            || weights.iter().any(|weight| weight.is_zero())
+            || weights.iter().try_fold(Decimal::zero(), |sum, weight| sum.checked_add(*weight)) != Ok(Decimal::one()) => {
+            return Err(ContractError::InvalidWeights {});
+        }
+        // Concentrated liquidity pools trade a single pair
+        PoolType::Concentrated { .. } if asset_count != 2 => return Err(ContractError::InvalidPoolType {}),
+        _ => {}
+    }
+
+    if msg.contract_config.max_referral_commission >= Decimal::one() {
+        return Err(ContractError::InvalidMaxReferralCommission {});
+    }
+
+    // The dynamic fee follows the price of a reserve based pool of a single pair
+    if let Some(dynamic_fee) = &msg.contract_config.dynamic_fee {
+        if asset_count != 2 || matches!(msg.contract_config.pool_type, PoolType::Concentrated { .. }) {
+            return Err(ContractError::InvalidDynamicFee {});
+        }
+        validate_dynamic_fee(dynamic_fee)?;
+    }
+
     let config = Config {
         lp_token_addr: lp_token_address.to_string(),
         fee_share: msg.contract_config.fee_share,
         tolerance_percentage: msg.contract_config.tolerance_percentage,
+        pool_type: msg.contract_config.pool_type,
+        position_nft_addr: position_nft_address.map(|addr| addr.to_string()),
+        dynamic_fee: msg.contract_config.dynamic_fee,
+        max_referral_commission: msg.contract_config.max_referral_commission,
     };
     CONFIG.save(deps.storage, &config)?;
+    let owner = msg.owner.map(|owner| deps.api.addr_validate(&owner)).transpose()?;
+    OWNER.save(deps.storage, &owner.unwrap_or_else(|| info.sender.clone()))?;
 
     // Set the initial pool reserves with the provided funding amounts
     let initial_reserves = PoolReserves {
-        asset1: msg.initial_funding_token1,
-        asset2: msg.initial_funding_token2,
+        assets: msg.initial_funding,
     };
     POOL_RESERVES.save(deps.storage, &initial_reserves)?;
 
@@ -29,15 +76,18 @@ pub fn set_up_contract(
     TOTAL_SUPPLY.save(deps.storage, &msg.initial_lp_token_supply)?;
 
     // Ensure that the correct funds are sent to match the initial pool funding
-    if !info.funds.iter().any(|coin| coin.denom == initial_reserves.asset1.denom && coin.amount == initial_reserves.asset1.amount)
-        || !info.funds.iter().any(|coin| coin.denom == initial_reserves.asset2.denom && coin.amount == initial_reserves.asset2.amount) {
+    if !initial_reserves.assets.iter().all(|asset| info.funds.contains(asset)) {
         return Err(ContractError::InvalidInitialFunds {});
     }
 
+    // Concentrated liquidity pools start with a full range position owned by the instantiator
+    if let PoolType::Concentrated { tick_spacing } = config.pool_type {
+        let mut reserves = initial_reserves.clone();
+        initialize_pool(deps.storage, &info.sender, &mut reserves, tick_spacing)?;
+        POOL_RESERVES.save(deps.storage, &reserves)?;
+    }
+
     Ok(Response::new()
         .add_attribute("method", "instantiate")
-        .add_attribute("initial_funding_token1_denom", initial_reserves.asset1.denom)
-        .add_attribute("initial_funding_token1_amount", initial_reserves.asset1.amount.to_string())
-        .add_attribute("initial_funding_token2_denom", initial_reserves.asset2.denom)
-        .add_attribute("initial_funding_token2_amount", initial_reserves.asset2.amount.to_string()))
+        .add_attribute("initial_funding", initial_reserves.assets.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",")))
 }
\ No newline at end of file
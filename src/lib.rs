@@ -1,7 +1,20 @@
+pub mod concentrated;
 pub mod contract;
+pub mod cost_basis;
+pub mod curve;
+pub mod dynamic_fee;
 pub mod error;
+pub mod events;
 pub mod execute;
+pub mod fee_discount;
+pub mod flash_loan;
+pub mod math;
 pub mod msg;
+pub mod owner;
+pub mod position_nft;
 pub mod query;
+pub mod referral;
 pub mod state;
+pub mod stats;
 mod instantiate;
+mod migrate;
@@ -0,0 +1,141 @@
+use cosmwasm_std::{CheckedFromRatioError, CheckedMultiplyRatioError, Decimal256, DivideByZeroError, OverflowError, OverflowOperation, StdError, StdResult, Uint128, Uint256, Uint512};
+
+// ln(2) truncated to 18 decimal places
+const LN_2: Decimal256 = Decimal256::raw(693_147_180_559_945_309);
+
+// Euler's number truncated to 18 decimal places
+const E: Decimal256 = Decimal256::raw(2_718_281_828_459_045_235);
+
+pub(crate) fn overflow(operation: OverflowOperation) -> StdError {
+    StdError::overflow(OverflowError::new(operation, "decimal", "decimal"))
+}
+
+pub(crate) fn divide_by_zero(dividend: impl ToString) -> StdError {
+    StdError::divide_by_zero(DivideByZeroError::new(dividend))
+}
+
+// Decimal256 division that fails instead of panicking on a zero divisor or overflow
+pub(crate) fn checked_div(dividend: Decimal256, divisor: Decimal256) -> StdResult<Decimal256> {
+    dividend.checked_div(divisor).map_err(|err| match err {
+        CheckedFromRatioError::DivideByZero => divide_by_zero(dividend),
+        CheckedFromRatioError::Overflow => overflow(OverflowOperation::Mul),
+    })
+}
+
+// Decimal256 product rounded up to the last decimal place instead of down
+pub(crate) fn checked_mul_ceil(a: Decimal256, b: Decimal256) -> StdResult<Decimal256> {
+    let one = Uint512::from(Decimal256::one().atomics());
+    let product = Uint512::from(a.atomics()).checked_mul(Uint512::from(b.atomics()))?;
+    let atomics = product.checked_add(one - Uint512::one())?.checked_div(one)?;
+    Ok(Decimal256::new(Uint256::try_from(atomics).map_err(|_| overflow(OverflowOperation::Mul))?))
+}
+
+// Decimal256 division rounded up to the last decimal place instead of down
+pub(crate) fn checked_div_ceil(dividend: Decimal256, divisor: Decimal256) -> StdResult<Decimal256> {
+    if divisor.is_zero() {
+        return Err(divide_by_zero(dividend));
+    }
+    let numerator = Uint512::from(dividend.atomics()).checked_mul(Uint512::from(Decimal256::one().atomics()))?;
+    let denominator = Uint512::from(divisor.atomics());
+    let atomics = numerator.checked_add(denominator - Uint512::one())?.checked_div(denominator)?;
+    Ok(Decimal256::new(Uint256::try_from(atomics).map_err(|_| overflow(OverflowOperation::Mul))?))
+}
+
+// value * numerator / denominator rounded down, failing instead of panicking on overflow or a zero denominator
+pub(crate) fn multiply_ratio(value: Uint128, numerator: Uint128, denominator: Uint128) -> StdResult<Uint128> {
+    value
+        .checked_multiply_ratio(numerator, denominator)
+        .map_err(|err| match err {
+            CheckedMultiplyRatioError::DivideByZero => divide_by_zero(value),
+            CheckedMultiplyRatioError::Overflow => overflow(OverflowOperation::Mul),
+        })
+}
+
+// Integer part of a decimal as an exponent for checked_pow
+fn integer_part(x: Decimal256) -> StdResult<u32> {
+    let whole = Uint128::try_from(x.to_uint_floor())?;
+    u32::try_from(whole.u128()).map_err(|_| overflow(OverflowOperation::Pow))
+}
+
+// Natural logarithm for x >= 1
+// The argument is reduced to m * 2^k with m in [1, 2) and ln(m) is computed with the
+// series ln(m) = 2 * (z + z^3/3 + z^5/5 + ...) where z = (m - 1) / (m + 1)
+fn ln_above_one(x: Decimal256) -> StdResult<Decimal256> {
+    let two = Decimal256::from_ratio(2u128, 1u128);
+
+    let mut mantissa = x;
+    let mut exponent = 0u32;
+    while mantissa >= two {
+        mantissa = checked_div(mantissa, two)?;
+        exponent += 1;
+    }
+
+    let z = checked_div(mantissa.checked_sub(Decimal256::one())?, mantissa.checked_add(Decimal256::one())?)?;
+    let z_squared = z.checked_mul(z)?;
+
+    let mut sum = Decimal256::zero();
+    let mut power = z;
+    let mut n = 1u128;
+    while !power.is_zero() {
+        sum = sum.checked_add(checked_div(power, Decimal256::from_ratio(n, 1u128))?)?;
+        power = power.checked_mul(z_squared)?;
+        n += 2;
+    }
+
+    let ln_mantissa = sum.checked_mul(two)?;
+    Ok(LN_2.checked_mul(Decimal256::from_ratio(exponent, 1u32))?.checked_add(ln_mantissa)?)
+}
+
+// e^y for y >= 0
+// e^y = e^n * e^f with n the integer part of y, and e^f computed with its Taylor series
+fn exp(y: Decimal256) -> StdResult<Decimal256> {
+    let whole = y.floor();
+    let fraction = y.checked_sub(whole)?;
+    let whole = integer_part(whole)?;
+
+    let mut sum = Decimal256::one();
+    let mut term = Decimal256::one();
+    let mut n = 1u128;
+    while !term.is_zero() {
+        term = checked_div(term.checked_mul(fraction)?, Decimal256::from_ratio(n, 1u128))?;
+        sum = sum.checked_add(term)?;
+        n += 1;
+    }
+
+    Ok(E.checked_pow(whole)?.checked_mul(sum)?)
+}
+
+/// Fixed-point `base ^ exponent` for a non-negative base and exponent.
+/// The integer part of the exponent is applied with exact repeated multiplication and the
+/// fractional part with exp(fraction * ln(base)).
+pub fn pow(base: Decimal256, exponent: Decimal256) -> StdResult<Decimal256> {
+    if exponent.is_zero() {
+        return Ok(Decimal256::one());
+    }
+    if base.is_zero() {
+        return Ok(Decimal256::zero());
+    }
+
+    let whole = exponent.floor();
+    let fraction = exponent.checked_sub(whole)?;
+    let whole = integer_part(whole)?;
+    let whole_power = base.checked_pow(whole)?;
+
+    if fraction.is_zero() {
+        return Ok(whole_power);
+    }
+
+    // For base < 1 the logarithm is negative, so compute 1 / (1 / base) ^ fraction instead
+    let fraction_power = if base >= Decimal256::one() {
+        exp(fraction.checked_mul(ln_above_one(base)?)?)?
+    } else {
+        let inverse = Decimal256::one()
+            .checked_div(base)
+            .map_err(|_| overflow(OverflowOperation::Pow))?;
+        Decimal256::one()
+            .checked_div(exp(fraction.checked_mul(ln_above_one(inverse)?)?)?)
+            .map_err(|_| overflow(OverflowOperation::Pow))?
+    };
+
+    Ok(whole_power.checked_mul(fraction_power)?)
+}
@@ -0,0 +1,103 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Decimal, DepsMut, Env, Order, Response, StdResult, to_json_binary, Uint128, WasmMsg};
+use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::{Item, Map};
+use semver::Version;
+use crate::error::ContractError;
+use crate::msg::{Config, MigrateMsg, PoolType};
+use crate::state::{CONFIG, OWNER, POOL_RESERVES, PoolReserves};
+
+// State layout of 0.1.0: a single pair, and shares kept in an internal ledger instead of LP token balances
+const LEGACY_VERSION: Version = Version::new(0, 1, 0);
+
+#[cw_serde]
+struct LegacyPoolReserves {
+    asset1: Coin,
+    asset2: Coin,
+}
+
+#[cw_serde]
+struct LegacyConfig {
+    lp_token_addr: String,
+    fee_share: Decimal,
+    tolerance_percentage: Decimal,
+}
+
+const LEGACY_POOL_RESERVES: Item<LegacyPoolReserves> = Item::new("pool_reserves");
+const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("Config");
+const LEGACY_SHARE_BALANCES: Map<&Addr, Uint128> = Map::new("share_balances");
+
+pub fn migrate_contract(
+    mut deps: DepsMut,
+    env: &Env,
+    stored_version: &str,
+    version: &str,
+    msg: MigrateMsg,
+) -> Result<Response, ContractError> {
+    // Only upgrades are allowed
+    let stored_version: Version = stored_version.parse().map_err(|_| ContractError::InvalidMigration {})?;
+    let version: Version = version.parse().map_err(|_| ContractError::InvalidMigration {})?;
+    if stored_version >= version {
+        return Err(ContractError::InvalidMigration {});
+    }
+
+    let mut response = Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored_version.to_string())
+        .add_attribute("to_version", version.to_string());
+
+    if stored_version <= LEGACY_VERSION {
+        response = response.add_messages(migrate_legacy_state(deps.branch(), env)?);
+    }
+
+    if let Some(owner) = msg.owner {
+        OWNER.save(deps.storage, &deps.api.addr_validate(&owner)?)?;
+    }
+
+    Ok(response)
+}
+
+// Rewrites the reserves and config in the current layout and mints the LP tokens of the share ledger to its
+// holders, the pool has to be the LP token's minter by then. The total supply already counts those shares.
+// 0.1.0 kept the swap fees out of the reserves, they're added to them now so they can't be skimmed
+fn migrate_legacy_state(deps: DepsMut, env: &Env) -> Result<Vec<CosmosMsg>, ContractError> {
+    let reserves = LEGACY_POOL_RESERVES.load(deps.storage)?;
+    let assets = [reserves.asset1, reserves.asset2]
+        .into_iter()
+        .map(|mut asset| {
+            let balance = deps.querier.query_balance(&env.contract.address, &asset.denom)?.amount;
+            asset.amount = asset.amount.max(balance);
+            Ok(asset)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    POOL_RESERVES.save(deps.storage, &PoolReserves { assets })?;
+
+    let config = LEGACY_CONFIG.load(deps.storage)?;
+    CONFIG.save(deps.storage, &Config {
+        lp_token_addr: config.lp_token_addr.clone(),
+        fee_share: config.fee_share,
+        tolerance_percentage: config.tolerance_percentage,
+        pool_type: PoolType::ConstantProduct,
+        position_nft_addr: None,
+        dynamic_fee: None,
+        max_referral_commission: Decimal::zero(),
+    })?;
+
+    let shares = LEGACY_SHARE_BALANCES
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mint_msgs = shares
+        .iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(holder, amount)| {
+            Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: config.lp_token_addr.clone(),
+                msg: to_json_binary(&Cw20ExecuteMsg::Mint { recipient: holder.to_string(), amount: *amount })?,
+                funds: vec![],
+            }))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    LEGACY_SHARE_BALANCES.clear(deps.storage);
+
+    Ok(mint_msgs)
+}
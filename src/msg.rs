@@ -1,33 +1,120 @@
 use cosmwasm_schema::{cw_serde};
-use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cosmwasm_std::{Addr, Binary, Coin, Decimal, Decimal256, Int128, Uint128};
 
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub initial_funding_token1: Coin,
-    pub initial_funding_token2: Coin,
+    /// Initial funding for each pool asset, between 2 and 8 assets. The order sets the asset order of the pool
+    pub initial_funding: Vec<Coin>,
     pub initial_lp_token_supply: Uint128,
+    /// Owner of the pool, who manages the fee discounts. The instantiator without one
+    pub owner: Option<String>,
     pub contract_config: Config,
 }
 
+#[cw_serde]
+pub struct MigrateMsg {
+    /// Sets the owner of the pool, pools migrated from 0.1.0 have none
+    pub owner: Option<String>,
+}
+
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Deposit any subset of the pool assets. Depositing every asset checks the deposit ratio,
+    /// a subset is priced against the invariant and pays the swap fee on the unbalanced part
     Deposit {
-        asset1: Coin,
-        asset2: Coin,
+        assets: Vec<Coin>,
     },
+    /// Burn LP tokens for a proportional share of every asset, or for a single asset.
+    /// The pool burns the tokens from the sender's balance, so it needs an allowance for them.
+    /// With `position_id`, the position NFT is burned instead and `amount_to_burn` has to be its
+    /// whole LP amount. The pool needs an approval for the NFT
     Withdraw {
         amount_to_burn: Uint128,
+        asset: Option<String>,
+        position_id: Option<String>,
     },
+    /// Swap the offered asset for the ask asset, which can be left out in two asset pools.
+    /// `referral_commission` of the offered amount is sent to `referral_address` and the rest is swapped
     Swap {
         offered_asset: Coin,
+        ask_denom: Option<String>,
+        referral_address: Option<String>,
+        referral_commission: Option<Decimal>,
+    },
+    /// Provide liquidity to a concentrated liquidity pool between two ticks
+    CreatePosition {
+        lower_tick: i32,
+        upper_tick: i32,
+        asset1: Coin,
+        asset2: Coin,
+    },
+    IncreaseLiquidity {
+        position_id: u64,
+        asset1: Coin,
+        asset2: Coin,
+    },
+    DecreaseLiquidity {
+        position_id: u64,
+        liquidity: Uint128,
+    },
+    /// Send the swap fees earned by a concentrated liquidity position to its owner
+    CollectFees {
+        position_id: u64,
+    },
+    /// Lend pool reserves to the sender, which is called with `callback_msg` after receiving them.
+    /// By the end of the callback the pool has to hold its reserves plus the swap fee on the
+    /// borrowed assets, otherwise the whole transaction reverts.
+    FlashLoan {
+        assets: Vec<Coin>,
+        callback_msg: Binary,
+    },
+    /// Set the reserves to the bank balances of the pool, adding tokens sent directly to the pool to the reserves
+    Sync {},
+    /// Send the bank balances above the reserves to `to`
+    Skim {
+        to: String,
+    },
+    /// Waive `discount` of the swap fee for swaps sent by `address`, or remove its discount. Owner only
+    SetFeeDiscount {
+        address: String,
+        discount: Option<Decimal>,
+    },
+    /// Transfer the ownership of the pool to `owner`. Owner only
+    UpdateOwner {
+        owner: String,
     },
 }
 
 #[cw_serde]
 pub enum QueryMsg {
     GetReserves {},
+    /// LP token balance of a user, zero for addresses that never held LP tokens
     GetUserShare { user: Addr },
+    /// LP token holders ordered by address, paginated through the LP token's account listing
+    AllShares { start_after: Option<String>, limit: Option<u32> },
     GetConfig {},
+    GetConcentratedPool {},
+    GetPosition { owner: Addr, position_id: u64 },
+    /// Amount received for swapping the offered asset at the current reserves
+    SimulateSwap { offered_asset: Coin, ask_denom: Option<String> },
+    /// Difference between the bank balances and the reserves of each asset
+    ReserveDrift {},
+    /// LP tokens minted for a deposit at the current reserves and whether `deposit` would accept it
+    SimulateDeposit { assets: Vec<Coin> },
+    /// Assets a user's LP tokens would be withdrawn for at the current reserves
+    ShareValue { user: Addr },
+    /// Assets `withdraw` pays out for burning `lp_amount` LP tokens, proportionally or all in `asset`
+    SimulateWithdraw { lp_amount: Uint128, asset: Option<String> },
+    /// The deposit recorded by a position NFT
+    PositionNft { position_id: String },
+    /// Value of a user's deposits now and if the deposited assets had been held instead, with the impermanent loss and fees earned
+    PositionPnl { user: Addr },
+    /// Cumulative swap volume, fees and number of swaps, deposits and withdrawals, with the hourly volume of the last 24 hours
+    PoolStats {},
+    /// Swap fee share charged to an address at the current price, after its fee discount
+    EffectiveFee { address: Addr },
+    /// Owner of the pool, none for pools migrated from 0.1.0 without one
+    Owner {},
 }
 #[cw_serde]
 pub struct Config {
@@ -37,13 +124,170 @@ pub struct Config {
     pub fee_share: Decimal,
     /// Tolerance percentage for verifying deposit ratio
     pub tolerance_percentage: Decimal,
+    /// The invariant used to price swaps and deposits
+    pub pool_type: PoolType,
+    /// cw721 contract minting a position NFT for each deposit instead of LP tokens, with the pool
+    /// as its minter. Without it deposits mint LP tokens
+    pub position_nft_addr: Option<String>,
+    /// Swap fee between bounds that follows the volatility of the pool price, instead of `fee_share`, for pools
+    /// of two assets that aren't concentrated. Deposits and withdrawals still charge `fee_share`
+    pub dynamic_fee: Option<DynamicFee>,
+    /// Highest share of the offered amount a swap can pay as referral commission, zero disables referrals
+    pub max_referral_commission: Decimal,
+}
+#[cw_serde]
+pub struct DynamicFee {
+    /// Swap fee when the price didn't move during the window
+    pub min_fee: Decimal,
+    /// Swap fee once the volatility reaches `max_volatility`
+    pub max_fee: Decimal,
+    /// Length in seconds of the window of swap prices the volatility is measured over
+    pub window: u64,
+    /// Volatility, (highest price - lowest price) / lowest price, that gets charged the maximum fee
+    pub max_volatility: Decimal,
+}
+#[cw_serde]
+pub enum PoolType {
+    /// Constant product pool (x * y = k), both assets hold the same share of the pool value
+    ConstantProduct,
+    /// Weighted product pool (x^w1 * y^w2 * ... = k), each asset holds its weight of the pool value.
+    /// One weight per asset in pool asset order, non-zero and adding up to one, e.g. 0.8 / 0.2
+    Weighted {
+        weights: Vec<Decimal>,
+    },
+    /// Concentrated liquidity pool of two assets where liquidity is provided within tick ranges.
+    /// Tick i has the price 1.0001^i (asset2 per asset1), position ticks must be multiples of tick_spacing
+    Concentrated {
+        tick_spacing: u32,
+    },
 }
 #[cw_serde]
 pub struct ReservesResponse {
-    pub asset1_reserve: Uint128,
-    pub asset2_reserve: Uint128,
+    pub reserves: Vec<Coin>,
 }
 #[cw_serde]
 pub struct UserShareResponse {
     pub user_share: Uint128,
 }
+
+#[cw_serde]
+pub struct AllSharesResponse {
+    pub shares: Vec<UserShare>,
+}
+
+#[cw_serde]
+pub struct UserShare {
+    pub user: Addr,
+    pub user_share: Uint128,
+}
+
+#[cw_serde]
+pub struct SimulateDepositResponse {
+    pub lp_amount: Uint128,
+    /// Deposit of each asset that matches the pool price given the deposit of the next asset.
+    /// Empty for a deposit of some of the assets, which isn't held to the pool price
+    pub ideal_deposits: Vec<Coin>,
+    /// Why `deposit` would reject the deposit, e.g. an invalid deposit ratio
+    pub error: Option<String>,
+}
+
+#[cw_serde]
+pub struct ShareValueResponse {
+    pub user_share: Uint128,
+    pub assets: Vec<Coin>,
+}
+
+/// Values are in units of asset2
+#[cw_serde]
+pub struct PositionPnlResponse {
+    pub user: Addr,
+    /// LP tokens of the deposits that haven't been withdrawn
+    pub lp_amount: Uint128,
+    pub deposits: Vec<Coin>,
+    /// Price of asset1 in asset2 at the time of the deposits, averaged by LP tokens
+    pub entry_price: Decimal256,
+    pub current_price: Decimal256,
+    /// Value of the assets the LP tokens would be withdrawn for
+    pub current_value: Uint128,
+    /// Value of the deposited assets
+    pub hold_value: Uint128,
+    /// Loss against holding from the move of the asset1 price since the entry, in percent
+    pub impermanent_loss: Decimal256,
+    /// Part of the current value from the swap fees
+    pub fees_earned: Uint128,
+}
+
+/// Volume and fees are amounts of the offered asset
+#[cw_serde]
+pub struct PoolStatsResponse {
+    pub volume: Vec<Coin>,
+    pub fees_collected: Vec<Coin>,
+    pub swap_count: u64,
+    pub deposit_count: u64,
+    pub withdraw_count: u64,
+    /// Volume of the last 24 hours, the sum of the hourly volume
+    pub volume_24h: Vec<Coin>,
+    /// Hours of the last 24 with swaps, oldest first
+    pub hourly_volume: Vec<HourlyVolume>,
+}
+
+#[cw_serde]
+pub struct HourlyVolume {
+    /// Start of the hour in seconds
+    pub start_time: u64,
+    pub volume: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct EffectiveFeeResponse {
+    pub address: Addr,
+    /// Swap fee share of the pool before the discount
+    pub fee_share: Decimal,
+    pub discount: Decimal,
+    /// fee_share * (1 - discount)
+    pub effective_fee_share: Decimal,
+}
+
+#[cw_serde]
+pub struct OwnerResponse {
+    pub owner: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct SimulateWithdrawResponse {
+    pub assets: Vec<Coin>,
+}
+
+#[cw_serde]
+pub struct SimulateSwapResponse {
+    pub return_asset: Coin,
+    pub fee_amount: Uint128,
+    /// Share of the offered amount charged as the fee
+    pub fee_share: Decimal,
+}
+
+#[cw_serde]
+pub struct ReserveDriftResponse {
+    pub assets: Vec<AssetDrift>,
+}
+
+#[cw_serde]
+pub struct AssetDrift {
+    pub denom: String,
+    pub reserve: Uint128,
+    pub balance: Uint128,
+    /// balance - reserve
+    pub drift: Int128,
+}
+
+/// Deposit recorded by a position NFT, also set as the NFT's extension
+#[cw_serde]
+pub struct PositionMetadata {
+    /// LP shares of the position
+    pub lp_amount: Uint128,
+    pub deposits: Vec<Coin>,
+    /// Spot price of the second pool asset per the first before the deposit
+    pub entry_price: Decimal256,
+    /// Block time of the deposit in seconds
+    pub timestamp: u64,
+}
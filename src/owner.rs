@@ -0,0 +1,22 @@
+use cosmwasm_std::{Addr, DepsMut, MessageInfo, Response, Storage};
+use crate::error::ContractError;
+use crate::state::OWNER;
+
+pub(crate) fn assert_owner(storage: &dyn Storage, sender: &Addr) -> Result<(), ContractError> {
+    if OWNER.may_load(storage)?.as_ref() != Some(sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(())
+}
+
+// Hand the ownership of the pool over to another address, owner only
+pub fn update_owner(deps: DepsMut, info: MessageInfo, owner: String) -> Result<Response, ContractError> {
+    assert_owner(deps.storage, &info.sender)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    OWNER.save(deps.storage, &owner)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "update_owner")
+        .add_attribute("owner", owner))
+}
@@ -0,0 +1,82 @@
+use cosmwasm_std::{Addr, Coin, CosmosMsg, Decimal256, Empty, Env, QuerierWrapper, StdError, Storage, to_json_binary, Uint128, WasmMsg};
+use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, OwnerOfResponse};
+use crate::error::ContractError;
+use crate::msg::{Config, PositionMetadata};
+use crate::state::{NEXT_POSITION_NFT_ID, POSITION_NFTS};
+
+// The position NFT contract of the pool, if deposits mint position NFTs
+fn position_nft_addr(config: &Config) -> Result<&str, ContractError> {
+    config.position_nft_addr.as_deref().ok_or(ContractError::PositionNftsDisabled {})
+}
+
+// Record a deposit and mint its position NFT to the depositor. Returns the token id and the mint message
+pub fn mint_position_nft(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+    lp_amount: Uint128,
+    deposits: Vec<Coin>,
+    entry_price: Decimal256,
+) -> Result<(String, CosmosMsg), ContractError> {
+    let contract_addr = position_nft_addr(config)?.to_string();
+
+    let id = NEXT_POSITION_NFT_ID.may_load(storage)?.unwrap_or_default();
+    NEXT_POSITION_NFT_ID.save(storage, &id.checked_add(1).ok_or_else(|| StdError::generic_err("Position NFT id overflow"))?)?;
+    let token_id = id.to_string();
+
+    let metadata = PositionMetadata {
+        lp_amount,
+        deposits,
+        entry_price,
+        timestamp: env.block.time.seconds(),
+    };
+    POSITION_NFTS.save(storage, &token_id, &metadata)?;
+
+    let mint_msg = cw721_base::ExecuteMsg::<PositionMetadata, Empty>::Mint {
+        token_id: token_id.clone(),
+        owner: recipient.to_string(),
+        token_uri: None,
+        extension: metadata,
+    };
+    let msg = WasmMsg::Execute {
+        contract_addr,
+        msg: to_json_binary(&mint_msg)?,
+        funds: vec![],
+    };
+    Ok((token_id, msg.into()))
+}
+
+// Check that the sender owns the position NFT and withdraws all of it, then remove its record and burn it.
+// The burn needs an approval for the pool from the owner
+pub fn burn_position_nft(
+    querier: &QuerierWrapper,
+    storage: &mut dyn Storage,
+    config: &Config,
+    sender: &Addr,
+    token_id: &str,
+    amount_to_burn: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    let contract_addr = position_nft_addr(config)?.to_string();
+
+    let owner: OwnerOfResponse = querier.query_wasm_smart(
+        &contract_addr,
+        &Cw721QueryMsg::OwnerOf { token_id: token_id.to_string(), include_expired: None },
+    )?;
+    if owner.owner != sender.as_str() {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let metadata = POSITION_NFTS.load(storage, token_id)?;
+    if amount_to_burn != metadata.lp_amount {
+        return Err(ContractError::InvalidPositionAmount {});
+    }
+    POSITION_NFTS.remove(storage, token_id);
+
+    let msg = WasmMsg::Execute {
+        contract_addr,
+        msg: to_json_binary(&Cw721ExecuteMsg::Burn { token_id: token_id.to_string() })?,
+        funds: vec![],
+    };
+    Ok(msg.into())
+}
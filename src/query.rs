@@ -1,23 +1,336 @@
-use crate::msg::{Config, ReservesResponse, UserShareResponse};
-use crate::state::{CONFIG, POOL_RESERVES, SHARE_BALANCES};
-use cosmwasm_std::{Addr, Deps, StdResult};
+use crate::concentrated;
+use crate::error::ContractError;
+use crate::curve::{pool_curve, PoolCurve};
+use crate::dynamic_fee::swap_fee_share;
+use crate::fee_discount::{discounted_fee_share, fee_discount};
+use crate::math::{checked_div, pow};
+use crate::execute::{bank_balances, check_liquidity, deposit_amounts, deposit_lp_amount, ideal_deposits, swap_amounts, swap_assets, validate_deposit, withdraw_amounts, withdrawn_coins};
+use crate::msg::{AllSharesResponse, AssetDrift, Config, EffectiveFeeResponse, HourlyVolume, OwnerResponse, PoolStatsResponse, PoolType, PositionMetadata, PositionPnlResponse, ReserveDriftResponse, ReservesResponse, ShareValueResponse, SimulateDepositResponse, SimulateSwapResponse, SimulateWithdrawResponse, UserShare, UserShareResponse};
+use crate::state::{CONCENTRATED_POOL, ConcentratedPool, CONFIG, COST_BASIS, OWNER, POOL_RESERVES, PoolReserves, Position, POSITION_NFTS, POSITIONS, TOTAL_SUPPLY, VOLUME_BUCKETS};
+use crate::stats::{first_volume_bucket, load_pool_stats, VOLUME_BUCKET_SECONDS};
+use cosmwasm_std::{Addr, Coin, Decimal256, Deps, Env, Int128, Order, StdError, StdResult, Uint128, Uint256};
+use cw_storage_plus::Bound;
+use cw20::{AllAccountsResponse, BalanceResponse, Cw20QueryMsg};
 
 pub fn query_reserves(deps: Deps) -> StdResult<ReservesResponse> {
     let pool_reserves = POOL_RESERVES.load(deps.storage)?;
     Ok(ReservesResponse {
-        asset1_reserve: pool_reserves.asset1.amount,
-        asset2_reserve: pool_reserves.asset2.amount,
+        reserves: pool_reserves.assets,
     })
 }
 
 pub fn query_user_share(deps: Deps, user: Addr) -> StdResult<UserShareResponse> {
-    let user_share = SHARE_BALANCES.load(deps.storage, &user.clone())?;
+    let config = CONFIG.load(deps.storage)?;
+    let user_share = query_lp_balance(deps, &config, &user)?;
     Ok(UserShareResponse {
         user_share,
     })
 }
 
+pub fn query_all_shares(deps: Deps, start_after: Option<String>, limit: Option<u32>) -> StdResult<AllSharesResponse> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // The LP token pages through its accounts, which keep an entry with a zero balance after
+    // everything was sent away. They're listed as well, so that every page has `limit` entries
+    let accounts: AllAccountsResponse = deps.querier.query_wasm_smart(
+        &config.lp_token_addr,
+        &Cw20QueryMsg::AllAccounts { start_after, limit },
+    )?;
+
+    let shares = accounts.accounts
+        .into_iter()
+        .map(|account| {
+            let user = deps.api.addr_validate(&account)?;
+            let user_share = query_lp_balance(deps, &config, &user)?;
+            Ok(UserShare { user, user_share })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(AllSharesResponse { shares })
+}
+
+// LP token balance of an address, the LP token is the only record of the shares
+pub(crate) fn query_lp_balance(deps: Deps, config: &Config, address: &Addr) -> StdResult<Uint128> {
+    let response: BalanceResponse = deps.querier.query_wasm_smart(
+        &config.lp_token_addr,
+        &Cw20QueryMsg::Balance { address: address.to_string() },
+    )?;
+    Ok(response.balance)
+}
+
 pub fn query_config(deps: Deps) -> StdResult<Config> {
     let config = CONFIG.load(deps.storage)?;
     Ok(config)
 }
+
+pub fn query_concentrated_pool(deps: Deps) -> StdResult<ConcentratedPool> {
+    let pool = CONCENTRATED_POOL.load(deps.storage)?;
+    Ok(pool)
+}
+
+pub fn query_position(deps: Deps, owner: Addr, position_id: u64) -> StdResult<Position> {
+    let position = POSITIONS.load(deps.storage, (&owner, position_id))?;
+    Ok(position)
+}
+
+pub fn query_simulate_swap(deps: Deps, env: Env, offered_asset: Coin, ask_denom: Option<String>) -> StdResult<SimulateSwapResponse> {
+    simulate_swap(deps, env, offered_asset, ask_denom).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn simulate_swap(deps: Deps, env: Env, offered_asset: Coin, ask_denom: Option<String>) -> Result<SimulateSwapResponse, ContractError> {
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
+    let (offer, ask) = swap_assets(&pool_reserves, &offered_asset.denom, ask_denom)?;
+
+    let (amount, fee_amount, fee_share) = match config.pool_type {
+        PoolType::Concentrated { .. } => {
+            let (amount, fee_amount) = concentrated::simulate_swap(deps.storage, &config, &offered_asset)?;
+            (amount, fee_amount, config.fee_share)
+        }
+        _ => {
+            let curve = pool_curve(&config.pool_type)?;
+            let fee_share = swap_fee_share(deps.storage, &env, &config, curve.as_ref(), &pool_reserves.amounts())?;
+            let (amount, fee_amount) = swap_amounts(&config, &pool_reserves.amounts(), offer, ask, offered_asset.amount, fee_share)?;
+            (amount, fee_amount, fee_share)
+        }
+    };
+
+    Ok(SimulateSwapResponse {
+        return_asset: Coin { denom: pool_reserves.assets[ask].denom.clone(), amount },
+        fee_amount,
+        fee_share,
+    })
+}
+
+pub fn query_reserve_drift(deps: Deps, env: Env) -> StdResult<ReserveDriftResponse> {
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let balances = bank_balances(&deps.querier, &env, &pool_reserves)?;
+
+    let assets = pool_reserves.assets
+        .into_iter()
+        .zip(balances)
+        .map(|(reserve, balance)| {
+            let drift = Int128::try_from(balance)?.checked_sub(Int128::try_from(reserve.amount)?)?;
+            Ok(AssetDrift {
+                denom: reserve.denom,
+                reserve: reserve.amount,
+                balance,
+                drift,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(ReserveDriftResponse { assets })
+}
+
+pub fn query_share_value(deps: Deps, user: Addr) -> StdResult<ShareValueResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let user_share = query_lp_balance(deps, &config, &user)?;
+    let withdrawal = query_simulate_withdraw(deps, user_share, None)?;
+    Ok(ShareValueResponse {
+        user_share,
+        assets: withdrawal.assets,
+    })
+}
+
+pub fn query_simulate_withdraw(deps: Deps, lp_amount: Uint128, asset: Option<String>) -> StdResult<SimulateWithdrawResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let total_supply = TOTAL_SUPPLY.load(deps.storage)?;
+
+    if lp_amount > total_supply {
+        return Err(StdError::generic_err("LP amount exceeds the total supply"));
+    }
+
+    let amounts = withdraw_amounts(&config, &pool_reserves, total_supply, lp_amount, asset)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(SimulateWithdrawResponse {
+        assets: withdrawn_coins(&pool_reserves, amounts),
+    })
+}
+
+pub fn query_simulate_deposit(deps: Deps, assets: Vec<Coin>) -> StdResult<SimulateDepositResponse> {
+    simulate_deposit(deps, assets).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn simulate_deposit(deps: Deps, assets: Vec<Coin>) -> Result<SimulateDepositResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let total_supply = TOTAL_SUPPLY.load(deps.storage)?;
+
+    let deposits = deposit_amounts(&pool_reserves, &assets)?;
+    let curve = pool_curve(&config.pool_type)?;
+    let reserves = pool_reserves.amounts();
+    check_liquidity(&reserves, total_supply)?;
+
+    let ideal_amounts = ideal_deposits(curve.as_ref(), &reserves, &deposits)?;
+    let lp_amount = deposit_lp_amount(curve.as_ref(), &config, &reserves, &deposits, total_supply)?;
+    let error = validate_deposit(&config, &deposits, &ideal_amounts, lp_amount).err().map(|err| err.to_string());
+
+    let ideal_deposits = pool_reserves.assets
+        .iter()
+        .zip(ideal_amounts)
+        .map(|(reserve, amount)| Coin { denom: reserve.denom.clone(), amount })
+        .collect();
+
+    Ok(SimulateDepositResponse {
+        lp_amount,
+        ideal_deposits,
+        error,
+    })
+}
+
+pub fn query_position_nft(deps: Deps, position_id: String) -> StdResult<PositionMetadata> {
+    POSITION_NFTS.load(deps.storage, &position_id)
+}
+
+pub fn query_position_pnl(deps: Deps, user: Addr) -> StdResult<PositionPnlResponse> {
+    position_pnl(deps, user).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn position_pnl(deps: Deps, user: Addr) -> Result<PositionPnlResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let total_supply = TOTAL_SUPPLY.load(deps.storage)?;
+
+    let curve = pool_curve(&config.pool_type)?;
+    let reserves = pool_reserves.amounts();
+    let current_price = curve.spot_price(&reserves, 0, 1)?;
+
+    let Some(cost_basis) = COST_BASIS.may_load(deps.storage, &user)? else {
+        return Ok(PositionPnlResponse {
+            user,
+            lp_amount: Uint128::zero(),
+            deposits: vec![],
+            entry_price: Decimal256::zero(),
+            current_price,
+            current_value: Uint128::zero(),
+            hold_value: Uint128::zero(),
+            impermanent_loss: Decimal256::zero(),
+            fees_earned: Uint128::zero(),
+        });
+    };
+
+    // Prices of the pool assets in asset2
+    let prices = (0..reserves.len())
+        .map(|asset| curve.spot_price(&reserves, asset, 1))
+        .collect::<StdResult<Vec<_>>>()?;
+    let withdrawn = curve.assets_for_withdraw(&reserves, cost_basis.lp_amount, total_supply)?;
+    let deposited: Vec<Uint128> = cost_basis.deposits.iter().map(|deposit| deposit.amount).collect();
+    let current_value = asset_value(&withdrawn, &prices)?;
+    let hold_value = asset_value(&deposited, &prices)?;
+
+    // Value of the position against holding if no fees had been earned, from the move of the asset1 price:
+    // r ^ w1 / (w1 * r + w2), with r = current_price / entry_price and the weights of asset1 and asset2
+    let value_ratio = lp_value_ratio(curve.as_ref(), reserves.len(), current_price, cost_basis.entry_price)?;
+    let impermanent_loss = Decimal256::one().checked_sub(value_ratio)?.checked_mul(Decimal256::from_ratio(100u128, 1u128))?;
+    let value_without_fees = Uint128::try_from(Uint256::from(hold_value).mul_floor(value_ratio)).map_err(StdError::from)?;
+    let fees_earned = current_value.saturating_sub(value_without_fees);
+
+    Ok(PositionPnlResponse {
+        user,
+        lp_amount: cost_basis.lp_amount,
+        deposits: cost_basis.deposits,
+        entry_price: cost_basis.entry_price,
+        current_price,
+        current_value,
+        hold_value,
+        impermanent_loss,
+        fees_earned,
+    })
+}
+
+// Value of asset amounts at the given prices, rounded down
+fn asset_value(amounts: &[Uint128], prices: &[Decimal256]) -> StdResult<Uint128> {
+    let mut value = Uint256::zero();
+    for (amount, price) in amounts.iter().zip(prices) {
+        value = value.checked_add(Uint256::from(*amount).mul_floor(*price))?;
+    }
+    Ok(Uint128::try_from(value)?)
+}
+
+// Value of an LP position relative to holding its deposit, for a price move of asset1 against asset2
+fn lp_value_ratio(curve: &dyn PoolCurve, asset_count: usize, current_price: Decimal256, entry_price: Decimal256) -> Result<Decimal256, ContractError> {
+    let weights = curve.weights(asset_count);
+    let weight1 = checked_div(weights[0], weights[0].checked_add(weights[1])?)?;
+    let weight2 = Decimal256::one().checked_sub(weight1)?;
+
+    let price_ratio = checked_div(current_price, entry_price)?;
+    let hold_ratio = weight1.checked_mul(price_ratio)?.checked_add(weight2)?;
+    // The ratio is at most one, the rounding of pow could put it just above
+    Ok(checked_div(pow(price_ratio, weight1)?, hold_ratio)?.min(Decimal256::one()))
+}
+
+pub fn query_pool_stats(deps: Deps, env: Env) -> StdResult<PoolStatsResponse> {
+    let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+    let stats = load_pool_stats(deps.storage, pool_reserves.assets.len())?;
+
+    // Buckets are only removed on swaps, so older ones can still be around
+    let buckets = VOLUME_BUCKETS
+        .range(deps.storage, Some(Bound::inclusive(first_volume_bucket(&env))), None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut volume_24h = vec![Uint128::zero(); pool_reserves.assets.len()];
+    for (_, volume) in &buckets {
+        for (total, amount) in volume_24h.iter_mut().zip(volume) {
+            *total = total.checked_add(*amount)?;
+        }
+    }
+
+    let hourly_volume = buckets
+        .into_iter()
+        .map(|(bucket, volume)| HourlyVolume {
+            start_time: bucket * VOLUME_BUCKET_SECONDS,
+            volume: pool_coins(&pool_reserves, volume),
+        })
+        .collect();
+
+    Ok(PoolStatsResponse {
+        volume: pool_coins(&pool_reserves, stats.volume),
+        fees_collected: pool_coins(&pool_reserves, stats.fees_collected),
+        swap_count: stats.swap_count,
+        deposit_count: stats.deposit_count,
+        withdraw_count: stats.withdraw_count,
+        volume_24h: pool_coins(&pool_reserves, volume_24h),
+        hourly_volume,
+    })
+}
+
+// Coins of every pool asset, zero amounts included
+fn pool_coins(pool_reserves: &PoolReserves, amounts: Vec<Uint128>) -> Vec<Coin> {
+    pool_reserves.assets
+        .iter()
+        .zip(amounts)
+        .map(|(reserve, amount)| Coin { denom: reserve.denom.clone(), amount })
+        .collect()
+}
+
+pub fn query_effective_fee(deps: Deps, env: Env, address: Addr) -> StdResult<EffectiveFeeResponse> {
+    effective_fee(deps, env, address).map_err(|err| StdError::generic_err(err.to_string()))
+}
+
+fn effective_fee(deps: Deps, env: Env, address: Addr) -> Result<EffectiveFeeResponse, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Concentrated liquidity pools always charge the fixed fee share
+    let fee_share = match config.pool_type {
+        PoolType::Concentrated { .. } => config.fee_share,
+        _ => {
+            let pool_reserves = POOL_RESERVES.load(deps.storage)?;
+            let curve = pool_curve(&config.pool_type)?;
+            swap_fee_share(deps.storage, &env, &config, curve.as_ref(), &pool_reserves.amounts())?
+        }
+    };
+    let discount = fee_discount(deps.storage, &address)?;
+
+    Ok(EffectiveFeeResponse {
+        effective_fee_share: discounted_fee_share(deps.storage, &address, fee_share)?,
+        address,
+        fee_share,
+        discount,
+    })
+}
+
+pub fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
+    Ok(OwnerResponse { owner: OWNER.may_load(deps.storage)? })
+}
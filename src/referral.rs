@@ -0,0 +1,49 @@
+use cosmwasm_std::{Addr, Api, BankMsg, Coin, Decimal, Response};
+use crate::error::ContractError;
+use crate::events::referral_event;
+use crate::msg::Config;
+
+// Commission of a swap sent to the frontend that referred it
+pub(crate) struct Referral {
+    pub address: Addr,
+    pub commission: Coin,
+}
+
+// Take the referral commission out of the offered asset, rounded down. Returns the rest of the
+// offered asset, which is swapped, and the referral if there's one
+pub(crate) fn take_referral_commission(
+    api: &dyn Api,
+    config: &Config,
+    offered_asset: Coin,
+    referral_address: Option<String>,
+    referral_commission: Option<Decimal>,
+) -> Result<(Coin, Option<Referral>), ContractError> {
+    let (address, commission_share) = match (referral_address, referral_commission) {
+        (None, None) => return Ok((offered_asset, None)),
+        (Some(address), commission_share) => (api.addr_validate(&address)?, commission_share.unwrap_or_default()),
+        (None, Some(_)) => return Err(ContractError::InvalidReferral {}),
+    };
+    if commission_share > config.max_referral_commission {
+        return Err(ContractError::InvalidReferral {});
+    }
+
+    let commission = Coin { denom: offered_asset.denom.clone(), amount: offered_asset.amount.mul_floor(commission_share) };
+    let swapped = Coin { denom: offered_asset.denom, amount: offered_asset.amount.checked_sub(commission.amount)? };
+    Ok((swapped, Some(Referral { address, commission })))
+}
+
+// Send the referral commission to the referrer along with the swap
+pub(crate) fn add_referral(response: Response, sender: &Addr, referral: Option<Referral>) -> Response {
+    let Some(referral) = referral else {
+        return response;
+    };
+
+    let response = response.add_event(referral_event(sender, &referral.address, &referral.commission));
+    if referral.commission.amount.is_zero() {
+        return response;
+    }
+    response.add_message(BankMsg::Send {
+        to_address: referral.address.to_string(),
+        amount: vec![referral.commission],
+    })
+}
@@ -1,22 +1,134 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Decimal256, Int128, SignedDecimal256, Uint128};
 use cw_storage_plus::{Item, Map};
-use crate::msg::Config;
+use crate::msg::{Config, PositionMetadata};
 
 // The current total LP token supply
 pub const TOTAL_SUPPLY: Item<Uint128> = Item::new("total_shares");
 
-// Balances of liquidity shares per address
-pub const SHARE_BALANCES: Map<&Addr, Uint128> = Map::new("share_balances");
-
 // Current pool reserves for each asset
 pub const POOL_RESERVES: Item<PoolReserves> = Item::new("pool_reserves");
 
 pub const CONFIG: Item<Config> = Item::new("Config");
 
-// Pool state to store the reserves using the Coin type
+// The instantiator of the pool, who manages the fee discounts
+pub const OWNER: Item<Addr> = Item::new("owner");
+
+// Share of the swap fee waived for an address, between zero and one
+pub const FEE_DISCOUNTS: Map<&Addr, Decimal> = Map::new("fee_discounts");
+
+// Price and active liquidity of a concentrated liquidity pool
+pub const CONCENTRATED_POOL: Item<ConcentratedPool> = Item::new("concentrated_pool");
+
+// Initialized ticks of a concentrated liquidity pool
+pub const TICKS: Map<i32, TickInfo> = Map::new("ticks");
+
+// Concentrated liquidity positions per owner and position id
+pub const POSITIONS: Map<(&Addr, u64), Position> = Map::new("positions");
+
+// The id assigned to the next concentrated liquidity position
+pub const NEXT_POSITION_ID: Item<u64> = Item::new("next_position_id");
+
+// Deposits of the position NFTs that haven't been withdrawn, by token id
+pub const POSITION_NFTS: Map<&str, PositionMetadata> = Map::new("position_nfts");
+
+// The token id of the next position NFT
+pub const NEXT_POSITION_NFT_ID: Item<u64> = Item::new("next_position_nft_id");
+
+// Deposited amounts and average entry price of each LP's deposits that haven't been withdrawn
+pub const COST_BASIS: Map<&Addr, CostBasis> = Map::new("cost_basis");
+
+// Cumulative swap volume, fees and number of swaps, deposits and withdrawals
+pub const POOL_STATS: Item<PoolStats> = Item::new("pool_stats");
+
+// Swap volume of each pool asset per hour, by hours since the epoch. Only the last 24 hours are kept
+pub const VOLUME_BUCKETS: Map<u64, Vec<Uint128>> = Map::new("volume_buckets");
+
+// Lowest and highest pool prices of the recent swaps per interval of the dynamic fee window, oldest first,
+// for the volatility of the dynamic fee
+pub const PRICE_BUCKETS: Item<Vec<PriceBucket>> = Item::new("price_buckets");
+
+// Fees owed on the flash loan in progress, one entry per pool asset. Set between the loan and its reply
+pub const FLASH_LOAN_FEES: Item<Vec<Uint128>> = Item::new("flash_loan_fees");
+
+// Pool state to store the reserves using the Coin type, one entry per pool asset
 #[cw_serde]
 pub struct PoolReserves {
-    pub asset1: Coin,
-    pub asset2: Coin,
-}
\ No newline at end of file
+    pub assets: Vec<Coin>,
+}
+
+impl PoolReserves {
+    /// Reserve amounts in pool asset order
+    pub fn amounts(&self) -> Vec<Uint128> {
+        self.assets.iter().map(|asset| asset.amount).collect()
+    }
+
+    /// Position of a denom in the pool assets
+    pub fn index_of(&self, denom: &str) -> Option<usize> {
+        self.assets.iter().position(|asset| asset.denom == denom)
+    }
+}
+
+// Lowest and highest price of asset1 in asset2 of the swaps in the interval starting at `start`
+#[cw_serde]
+pub struct PriceBucket {
+    pub start: u64,
+    pub low: Decimal256,
+    pub high: Decimal256,
+}
+
+// Volume and fees are amounts of the offered asset, one entry per pool asset
+#[cw_serde]
+pub struct PoolStats {
+    pub volume: Vec<Uint128>,
+    pub fees_collected: Vec<Uint128>,
+    pub swap_count: u64,
+    pub deposit_count: u64,
+    pub withdraw_count: u64,
+}
+
+#[cw_serde]
+pub struct CostBasis {
+    /// LP tokens minted by the deposits, less the withdrawn ones
+    pub lp_amount: Uint128,
+    /// Deposited amount of each pool asset, reduced in proportion on withdrawals
+    pub deposits: Vec<Coin>,
+    /// Price of asset1 in asset2 at the time of the deposits, averaged by LP tokens
+    pub entry_price: Decimal256,
+}
+
+// Concentrated liquidity pool state. Prices are asset2 per asset1
+#[cw_serde]
+pub struct ConcentratedPool {
+    pub sqrt_price: Decimal256,
+    pub current_tick: i32,
+    /// Liquidity of the positions whose range contains the current tick
+    pub liquidity: Uint128,
+    /// Swap fees earned per unit of liquidity over the life of the pool
+    pub fee_growth_global1: Decimal256,
+    pub fee_growth_global2: Decimal256,
+}
+
+#[cw_serde]
+pub struct TickInfo {
+    /// Total liquidity of the positions using this tick as a boundary
+    pub liquidity_gross: Uint128,
+    /// Liquidity added to the active liquidity when the price crosses this tick upwards
+    pub liquidity_net: Int128,
+    /// Fee growth on the other side of this tick from the current tick
+    pub fee_growth_outside1: Decimal256,
+    pub fee_growth_outside2: Decimal256,
+}
+
+#[cw_serde]
+pub struct Position {
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+    pub liquidity: Uint128,
+    /// Fee growth inside the position range as of the last update
+    pub fee_growth_inside1_last: SignedDecimal256,
+    pub fee_growth_inside2_last: SignedDecimal256,
+    /// Fees earned and not yet collected
+    pub tokens_owed1: Uint128,
+    pub tokens_owed2: Uint128,
+}
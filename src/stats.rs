@@ -0,0 +1,71 @@
+use cosmwasm_std::{Env, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::Bound;
+use crate::error::ContractError;
+use crate::state::{POOL_STATS, PoolStats, VOLUME_BUCKETS};
+
+// Length of a volume bucket and the number of buckets in the rolling volume, 24 hours
+pub const VOLUME_BUCKET_SECONDS: u64 = 3600;
+pub const VOLUME_BUCKET_COUNT: u64 = 24;
+
+// Volume bucket of a block time
+pub(crate) fn volume_bucket(env: &Env) -> u64 {
+    env.block.time.seconds() / VOLUME_BUCKET_SECONDS
+}
+
+// The first volume bucket of the rolling volume
+pub(crate) fn first_volume_bucket(env: &Env) -> u64 {
+    volume_bucket(env).saturating_sub(VOLUME_BUCKET_COUNT - 1)
+}
+
+// Pool stats so far, with zero volume and fees for every pool asset before the first update
+pub(crate) fn load_pool_stats(storage: &dyn Storage, asset_count: usize) -> StdResult<PoolStats> {
+    Ok(POOL_STATS.may_load(storage)?.unwrap_or_else(|| PoolStats {
+        volume: vec![Uint128::zero(); asset_count],
+        fees_collected: vec![Uint128::zero(); asset_count],
+        swap_count: 0,
+        deposit_count: 0,
+        withdraw_count: 0,
+    }))
+}
+
+// Add a swap of `offer_amount` of the offer asset, paying `fee` of it, to the pool stats and the
+// volume bucket of the block. Buckets that left the rolling volume are removed
+pub(crate) fn record_swap(
+    storage: &mut dyn Storage,
+    env: &Env,
+    asset_count: usize,
+    offer: usize,
+    offer_amount: Uint128,
+    fee: Uint128,
+) -> Result<(), ContractError> {
+    let mut stats = load_pool_stats(storage, asset_count)?;
+    stats.volume[offer] = stats.volume[offer].checked_add(offer_amount)?;
+    stats.fees_collected[offer] = stats.fees_collected[offer].checked_add(fee)?;
+    stats.swap_count += 1;
+    POOL_STATS.save(storage, &stats)?;
+
+    let bucket = volume_bucket(env);
+    let mut volume = VOLUME_BUCKETS.may_load(storage, bucket)?.unwrap_or_else(|| vec![Uint128::zero(); asset_count]);
+    volume[offer] = volume[offer].checked_add(offer_amount)?;
+    VOLUME_BUCKETS.save(storage, bucket, &volume)?;
+
+    let expired = VOLUME_BUCKETS
+        .keys(storage, None, Some(Bound::exclusive(first_volume_bucket(env))), Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for bucket in expired {
+        VOLUME_BUCKETS.remove(storage, bucket);
+    }
+    Ok(())
+}
+
+pub(crate) fn count_deposit(storage: &mut dyn Storage, asset_count: usize) -> StdResult<()> {
+    let mut stats = load_pool_stats(storage, asset_count)?;
+    stats.deposit_count += 1;
+    POOL_STATS.save(storage, &stats)
+}
+
+pub(crate) fn count_withdraw(storage: &mut dyn Storage, asset_count: usize) -> StdResult<()> {
+    let mut stats = load_pool_stats(storage, asset_count)?;
+    stats.withdraw_count += 1;
+    POOL_STATS.save(storage, &stats)
+}
@@ -1,7 +1,47 @@
-use cosmwasm_std::{Addr, coin, Decimal, Uint128};
-use cw_multi_test::{App, BankSudo, Contract, ContractWrapper, Executor, SudoMsg};
-use amm_contract::msg::{Config, ExecuteMsg, InstantiateMsg, ReservesResponse, UserShareResponse};
-use amm_contract::msg::QueryMsg::{GetConfig, GetReserves, GetUserShare};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, coin, Coin, Decimal, Decimal256, DepsMut, DivideByZeroError, Empty, Env, MessageInfo, Response, StdError, StdResult, Uint128};
+use cw_storage_plus::{Item, Map};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use amm_contract::error::ContractError;
+use amm_contract::msg::{AllSharesResponse, Config, DynamicFee, EffectiveFeeResponse, ExecuteMsg, HourlyVolume, InstantiateMsg, MigrateMsg, OwnerResponse, PoolStatsResponse, PoolType, PositionPnlResponse, ReserveDriftResponse, ReservesResponse, ShareValueResponse, SimulateDepositResponse, SimulateSwapResponse, SimulateWithdrawResponse, UserShareResponse};
+use amm_contract::msg::QueryMsg::{AllShares, EffectiveFee, GetConfig, GetReserves, GetUserShare, Owner, PoolStats, PositionPnl, ReserveDrift, ShareValue, SimulateDeposit, SimulateSwap, SimulateWithdraw};
+
+// LP token whose initial supply of 100 is held by the owner, who can mint until the pool takes over
+fn instantiate_lp_token(app: &mut App) -> Addr {
+    let code = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &cw20_base::msg::InstantiateMsg {
+            name: "AMM LP token".to_string(),
+            symbol: "AMMLP".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin { address: "owner".to_string(), amount: Uint128::new(100) }],
+            mint: Some(MinterResponse { minter: "owner".to_string(), cap: None }),
+            marketing: None,
+        },
+        &[],
+        "mock-lp-token",
+        None,
+    ).unwrap()
+}
+
+// Let the pool mint LP tokens for deposits
+fn set_lp_minter(app: &mut App, lp_token: &Addr, pool: &Addr) {
+    app.execute_contract(
+        Addr::unchecked("owner"),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::UpdateMinter { new_minter: Some(pool.to_string()) },
+        &[],
+    ).unwrap();
+}
 
 #[test]
 fn instantiate_success() {
@@ -25,13 +65,17 @@ fn instantiate_success() {
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(100000,"asset1"),
-            initial_funding_token2: coin(100000,"asset2"),
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
                 lp_token_addr: "lp_tokens".to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(100000,"asset1"), coin(100000,"asset2")],
@@ -48,14 +92,17 @@ fn instantiate_success() {
             lp_token_addr: "lp_tokens".to_string(),
             fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
             tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+            pool_type: PoolType::ConstantProduct,
+            position_nft_addr: None,
+            dynamic_fee: None,
+            max_referral_commission: Decimal::zero(),
         }
     );
 
     assert_eq!(
         pool_reserves,
         ReservesResponse {
-            asset1_reserve: Uint128::new(100000),
-            asset2_reserve: Uint128::new(100000),
+            reserves: vec![coin(100000,"asset1"), coin(100000,"asset2")],
         }
     );
 }
@@ -84,30 +131,36 @@ fn successful_deposit() {
     }))
         .unwrap();
 
+    let lp_token = instantiate_lp_token(&mut app);
+
     let addr = app.instantiate_contract(
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1000,"asset1"),
-            initial_funding_token2: coin(1000,"asset2"),
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
-                lp_token_addr: "lp_tokens".to_string(),
+                lp_token_addr: lp_token.to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1000,"asset1"), coin(1000,"asset2")],
         "mock-amm-contract",
         None,
     ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
 
     app.execute_contract(
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(1000000,"asset1"),
-            asset2: coin(1000000,"asset2"),
+            assets: vec![coin(1000000,"asset1"), coin(1000000,"asset2")],
         },
         &[coin(1000000,"asset1"), coin(1000000,"asset2")]
     ).unwrap();
@@ -150,13 +203,17 @@ fn deposit_invalid_funds_error() {
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1000,"asset1"),
-            initial_funding_token2: coin(1000,"asset2"),
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
                 lp_token_addr: "lp_tokens".to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1000,"asset1"), coin(1000,"asset2")],
@@ -168,8 +225,7 @@ fn deposit_invalid_funds_error() {
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(1000000,"asset1"),
-            asset2: coin(1000000,"asset2"),
+            assets: vec![coin(1000000,"asset1"), coin(1000000,"asset2")],
         },
         &[coin(1000000,"asset1")]
     ).unwrap_err();
@@ -203,13 +259,17 @@ fn deposit_ratio_tolerance_exceeded() {
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1000,"asset1"),
-            initial_funding_token2: coin(1000,"asset2"),
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
                 lp_token_addr: "lp_tokens".to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1000,"asset1"), coin(1000,"asset2")],
@@ -221,8 +281,7 @@ fn deposit_ratio_tolerance_exceeded() {
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(1000000,"asset1"),
-            asset2: coin(100,"asset2"),
+            assets: vec![coin(1000000,"asset1"), coin(100,"asset2")],
         },
         &[coin(1000000,"asset1"), coin(100,"asset2")]
     ).unwrap_err();
@@ -252,30 +311,36 @@ fn successful_deposit_more_complex_pool_ratio() {
     }))
         .unwrap();
 
+    let lp_token = instantiate_lp_token(&mut app);
+
     let addr = app.instantiate_contract(
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1500,"asset1"),
-            initial_funding_token2: coin(3000,"asset2"),
+            initial_funding: vec![coin(1500,"asset1"), coin(3000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
-                lp_token_addr: "lp_tokens".to_string(),
+                lp_token_addr: lp_token.to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1500,"asset1"), coin(3000,"asset2")],
         "mock-amm-contract",
         None,
     ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
 
     app.execute_contract(
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(200,"asset1"),
-            asset2: coin(400,"asset2"),
+            assets: vec![coin(200,"asset1"), coin(400,"asset2")],
         },
         &[coin(200,"asset1"), coin(400,"asset2")]
     ).unwrap();
@@ -305,7 +370,6 @@ fn attempting_to_withdraw_more_than_owned() {
 
     let owner = Addr::unchecked("owner");
     let depositor = Addr::unchecked("depositor");
-    let lp_contract_addr = Addr::unchecked("lp_tokens");
 
     app.sudo(SudoMsg::Bank(BankSudo::Mint {
         to_address: owner.to_string(),
@@ -319,36 +383,36 @@ fn attempting_to_withdraw_more_than_owned() {
     }))
         .unwrap();
 
-    app.sudo(SudoMsg::Bank(BankSudo::Mint {
-        to_address: lp_contract_addr.to_string(),
-        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2"), coin(100000000,"lp_tokens")],
-    }))
-        .unwrap();
+    let lp_contract_addr = instantiate_lp_token(&mut app);
 
     let addr = app.instantiate_contract(
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1000,"asset1"),
-            initial_funding_token2: coin(1000,"asset2"),
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
                 lp_token_addr: lp_contract_addr.to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1000,"asset1"), coin(1000,"asset2")],
         "mock-amm-contract",
         None,
     ).unwrap();
+    set_lp_minter(&mut app, &lp_contract_addr, &addr);
 
     app.execute_contract(
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(1000000,"asset1"),
-            asset2: coin(1000000,"asset2"),
+            assets: vec![coin(1000000,"asset1"), coin(1000000,"asset2")],
         },
         &[coin(1000000,"asset1"), coin(1000000,"asset2")]
     ).unwrap();
@@ -358,6 +422,8 @@ fn attempting_to_withdraw_more_than_owned() {
         addr.clone(),
         &ExecuteMsg::Withdraw {
             amount_to_burn: Uint128::new(10000000),
+            asset: None,
+            position_id: None,
         },
         &[]
     ).unwrap_err();
@@ -391,13 +457,17 @@ fn successful_swap() {
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(100000,"asset1"),
-            initial_funding_token2: coin(100000,"asset2"),
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
                 lp_token_addr: "lp_tokens".to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(100000,"asset1"), coin(100000,"asset2")],
@@ -410,8 +480,7 @@ fn successful_swap() {
     assert_eq!(
         pool_reserves,
         ReservesResponse {
-            asset1_reserve: Uint128::new(100000),
-            asset2_reserve: Uint128::new(100000),
+            reserves: vec![coin(100000,"asset1"), coin(100000,"asset2")],
         }
     );
 
@@ -420,22 +489,97 @@ fn successful_swap() {
         addr.clone(),
         &ExecuteMsg::Swap {
             offered_asset: coin(100, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
         },
         &[coin(100,"asset1")]
     ).unwrap();
 
     let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
 
-    // Assert pool ratio was accurately updated
+    // Assert pool ratio was accurately updated. The fee of 0.3 rounds up to 1 and 99 asset1 are swapped,
+    // the output of 100000 - 10^10 / 100099 = 98.9 rounds down. The fee stays in the reserves for the LPs
     assert_eq!(
         pool_reserves,
         ReservesResponse {
-            asset1_reserve: Uint128::new(100100),
-            asset2_reserve: Uint128::new(99900),
+            reserves: vec![coin(100100,"asset1"), coin(99902,"asset2")],
         }
     );
 
+    // So the reserves match the bank balances and skimming can't take the fee
+    let drift: ReserveDriftResponse = app.wrap().query_wasm_smart(addr.clone(), &ReserveDrift {}).unwrap();
+    assert!(drift.assets.iter().all(|asset| asset.reserve == asset.balance));
+    app.execute_contract(swapper.clone(), addr.clone(), &ExecuteMsg::Skim { to: swapper.to_string() }, &[]).unwrap();
+    assert_eq!(app.wrap().query_balance(&addr, "asset1").unwrap().amount, Uint128::new(100100));
+
+}
+#[test]
+fn swap_requires_the_offered_asset_as_funds() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let swap = |app: &mut App, funds: &[Coin]| {
+        app.execute_contract(
+            owner.clone(),
+            addr.clone(),
+            &ExecuteMsg::Swap {
+                offered_asset: coin(100, "asset1"),
+                ask_denom: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+            funds,
+        )
+    };
+
+    // No funds, too few, the wrong denom or extra coins are all rejected
+    for funds in [vec![], vec![coin(99,"asset1")], vec![coin(100,"asset2")], vec![coin(100,"asset1"), coin(1,"asset2")]] {
+        let err = swap(&mut app, &funds).unwrap_err();
+        assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidFunds {})));
+    }
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(100000,"asset1"), coin(100000,"asset2")]);
+
+    swap(&mut app, &[coin(100,"asset1")]).unwrap();
 }
+
 #[test]
 fn query_pool_data_and_contract_data() {
     let mut app = App::default();
@@ -461,30 +605,36 @@ fn query_pool_data_and_contract_data() {
     }))
         .unwrap();
 
+    let lp_token = instantiate_lp_token(&mut app);
+
     let addr = app.instantiate_contract(
         code_id,
         Addr::unchecked("owner"),
         &InstantiateMsg {
-            initial_funding_token1: coin(1000,"asset1"),
-            initial_funding_token2: coin(1000,"asset2"),
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
             initial_lp_token_supply: Uint128::new(100),
+            owner: None,
             contract_config: Config {
-                lp_token_addr: "lp_tokens".to_string(),
+                lp_token_addr: lp_token.to_string(),
                 fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
                 tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
             },
         },
         &[coin(1000,"asset1"), coin(1000,"asset2")],
         "mock-amm-contract",
         None,
     ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
 
     app.execute_contract(
         depositor.clone(),
         addr.clone(),
         &ExecuteMsg::Deposit {
-            asset1: coin(1000000,"asset1"),
-            asset2: coin(1000000,"asset2"),
+            assets: vec![coin(1000000,"asset1"), coin(1000000,"asset2")],
         },
         &[coin(1000000,"asset1"), coin(1000000,"asset2")]
     ).unwrap();
@@ -503,17 +653,1501 @@ fn query_pool_data_and_contract_data() {
     assert_eq!(
         pool_reserves,
         ReservesResponse {
-            asset1_reserve: Uint128::new(1001000),
-            asset2_reserve: Uint128::new(1001000),
+            reserves: vec![coin(1001000,"asset1"), coin(1001000,"asset2")],
         }
     );
 
     assert_eq!(
         config,
         Config {
-            lp_token_addr: "lp_tokens".to_string(),
+            lp_token_addr: lp_token.to_string(),
             fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
             tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+            pool_type: PoolType::ConstantProduct,
+            position_nft_addr: None,
+            dynamic_fee: None,
+            max_referral_commission: Decimal::zero(),
+        }
+    );
+}#[test]
+fn instantiate_weighted_pool_invalid_weights() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    // Weights that don't add up to one are rejected
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(80000,"asset1"), coin(20000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::Weighted {
+                    weights: vec![Decimal::percent(80), Decimal::percent(30)],
+                },
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(80000,"asset1"), coin(20000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap_err();
+}
+#[test]
+fn successful_weighted_pool_swap() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let swapper = Addr::unchecked("depositor");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: swapper.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    // 80/20 pool where both assets have the same price
+    let addr = app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(80000,"asset1"), coin(20000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::Weighted {
+                    weights: vec![Decimal::percent(80), Decimal::percent(20)],
+                },
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(80000,"asset1"), coin(20000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    app.execute_contract(
+        swapper.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(1000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(1000,"asset1")]
+    ).unwrap();
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+
+    // 997 asset1 are swapped after the fee, 20000 * (1 - (80000 / 80997) ^ (0.8 / 0.2)) = 966.69 asset2 go out.
+    // The fee stays in the reserves
+    assert_eq!(
+        pool_reserves,
+        ReservesResponse {
+            reserves: vec![coin(81000,"asset1"), coin(19034,"asset2")],
+        }
+    );
+
+    app.execute_contract(
+        swapper.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(1000, "asset2"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(1000,"asset2")]
+    ).unwrap();
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+
+    // 997 asset2 are swapped after the fee, 81000 * (1 - (19034 / 20031) ^ (0.2 / 0.8)) = 1027.28 asset1 go out
+    assert_eq!(
+        pool_reserves,
+        ReservesResponse {
+            reserves: vec![coin(79973,"asset1"), coin(20034,"asset2")],
+        }
+    );
+}
+#[test]
+fn weighted_pool_deposit_ratio() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: depositor.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(80000,"asset1"), coin(20000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::Weighted {
+                    weights: vec![Decimal::percent(80), Decimal::percent(20)],
+                },
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(80000,"asset1"), coin(20000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    // The simulation shows the deposits that match the 80/20 weights
+    let simulation: SimulateDepositResponse = app.wrap().query_wasm_smart(addr.clone(), &SimulateDeposit {
+        assets: vec![coin(8000,"asset1"), coin(8000,"asset2")],
+    }).unwrap();
+    assert_eq!(simulation.ideal_deposits, vec![coin(32000,"asset1"), coin(2000,"asset2")]);
+    assert_eq!(simulation.error, Some("Invalid deposit ratio".to_string()));
+
+    let simulation: SimulateDepositResponse = app.wrap().query_wasm_smart(addr.clone(), &SimulateDeposit {
+        assets: vec![coin(8000,"asset1"), coin(2000,"asset2")],
+    }).unwrap();
+    assert_eq!(simulation.lp_amount, Uint128::new(10));
+    assert_eq!(simulation.error, None);
+
+    // An equal value deposit doesn't match the 80/20 weights
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(8000,"asset1"), coin(8000,"asset2")],
+        },
+        &[coin(8000,"asset1"), coin(8000,"asset2")]
+    ).unwrap_err();
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(8000,"asset1"), coin(2000,"asset2")],
+        },
+        &[coin(8000,"asset1"), coin(2000,"asset2")]
+    ).unwrap();
+
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: (depositor) }).unwrap();
+
+    // 100 * (8000 / 80000) = 10
+    assert_eq!(
+        user_share,
+        UserShareResponse {
+            user_share: Uint128::new(10),
         }
     );
-}
\ No newline at end of file
+}
+
+#[test]
+fn three_asset_pool_swap_and_single_asset_deposit() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+
+    for user in [&owner, &depositor] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2"), coin(100000000,"asset3")],
+        }))
+            .unwrap();
+    }
+
+    let lp_token = instantiate_lp_token(&mut app);
+    let config = Config {
+        lp_token_addr: lp_token.to_string(),
+        fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+        tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+        pool_type: PoolType::ConstantProduct,
+        position_nft_addr: None,
+        dynamic_fee: None,
+        max_referral_commission: Decimal::zero(),
+    };
+
+    // A pool needs at least two assets
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: config.clone(),
+        },
+        &[coin(100000,"asset1")],
+        "mock-amm-contract",
+        None,
+    ).unwrap_err();
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2"), coin(100000,"asset3")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: config,
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2"), coin(100000,"asset3")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    // The ask asset has to be named when there are more than two assets
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(1000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(1000,"asset1")]
+    ).unwrap_err();
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(1000, "asset1"),
+            ask_denom: Some("asset3".to_string()),
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(1000,"asset1")]
+    ).unwrap();
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(
+        pool_reserves,
+        ReservesResponse {
+            reserves: vec![coin(101000,"asset1"), coin(100000,"asset2"), coin(99013,"asset3")],
+        }
+    );
+
+    // A deposit of a single asset is priced against the invariant
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(30000,"asset2")],
+        },
+        &[coin(30000,"asset2")]
+    ).unwrap();
+
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: (depositor) }).unwrap();
+
+    // 100 * ((130000 / 100000) ^ (1/3) - 1) = 9.1, less the fee on the unbalanced part
+    assert_eq!(
+        user_share,
+        UserShareResponse {
+            user_share: Uint128::new(9),
+        }
+    );
+}
+
+#[test]
+fn sync_and_skim_reconcile_reserves() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    // Tokens sent directly to the pool aren't part of the reserves
+    app.send_tokens(owner.clone(), addr.clone(), &[coin(500,"asset1")]).unwrap();
+
+    let drift: ReserveDriftResponse = app.wrap().query_wasm_smart(addr.clone(), &ReserveDrift {}).unwrap();
+    assert_eq!(drift.assets[0].balance, Uint128::new(100500));
+    assert_eq!(drift.assets[0].drift.i128(), 500);
+    assert_eq!(drift.assets[1].drift.i128(), 0);
+
+    let res = app.execute_contract(owner.clone(), addr.clone(), &ExecuteMsg::Skim { to: "skimmer".to_string() }, &[]).unwrap();
+    assert_eq!(event_attributes(&res, "wasm-reserves"), attributes(&[("action", "skim"), ("reserves", "100000asset1,100000asset2")]));
+
+    let balance = app.wrap().query_balance("skimmer", "asset1").unwrap();
+    assert_eq!(balance.amount, Uint128::new(500));
+    let drift: ReserveDriftResponse = app.wrap().query_wasm_smart(addr.clone(), &ReserveDrift {}).unwrap();
+    assert!(drift.assets.iter().all(|asset| asset.drift.is_zero()));
+
+    // Sync adds them to the reserves instead
+    app.send_tokens(owner.clone(), addr.clone(), &[coin(300,"asset2")]).unwrap();
+    let res = app.execute_contract(owner.clone(), addr.clone(), &ExecuteMsg::Sync {}, &[]).unwrap();
+    assert_eq!(event_attributes(&res, "wasm-reserves"), attributes(&[("action", "sync"), ("reserves", "100000asset1,100300asset2")]));
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(100000,"asset1"), coin(100300,"asset2")]);
+}
+
+#[test]
+fn withdraw_burns_lp_tokens_of_any_holder() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+    let holder = Addr::unchecked("holder");
+
+    for user in [&owner, &depositor] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+        }))
+            .unwrap();
+    }
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(1000,"asset1"), coin(1000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(10000,"asset1"), coin(10000,"asset2")],
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+
+    // The deposit mints LP tokens, which the depositor passes on
+    app.execute_contract(
+        depositor.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::Transfer { recipient: holder.to_string(), amount: Uint128::new(400) },
+        &[]
+    ).unwrap();
+
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: holder.clone() }).unwrap();
+    assert_eq!(user_share.user_share, Uint128::new(400));
+
+    // Holders are listed by address, in pages
+    let page: AllSharesResponse = app.wrap().query_wasm_smart(addr.clone(), &AllShares { start_after: None, limit: Some(2) }).unwrap();
+    let holders: Vec<_> = page.shares.iter().map(|share| (share.user.to_string(), share.user_share.u128())).collect();
+    assert_eq!(holders, vec![("depositor".to_string(), 600), ("holder".to_string(), 400)]);
+
+    let page: AllSharesResponse = app.wrap().query_wasm_smart(addr.clone(), &AllShares { start_after: Some("holder".to_string()), limit: None }).unwrap();
+    let holders: Vec<_> = page.shares.iter().map(|share| (share.user.to_string(), share.user_share.u128())).collect();
+    assert_eq!(holders, vec![("owner".to_string(), 100)]);
+
+    // Addresses that never held LP tokens have no share
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: Addr::unchecked("stranger") }).unwrap();
+    assert_eq!(user_share.user_share, Uint128::zero());
+
+    // The pool burns the LP tokens from the holder's balance through an allowance
+    app.execute_contract(
+        holder.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw {
+            amount_to_burn: Uint128::new(400),
+            asset: None,
+            position_id: None,
+        },
+        &[]
+    ).unwrap_err();
+
+    // 11000 * 400 / 1100 = 4000 of each asset
+    let share_value: ShareValueResponse = app.wrap().query_wasm_smart(addr.clone(), &ShareValue { user: holder.clone() }).unwrap();
+    assert_eq!(share_value.user_share, Uint128::new(400));
+    assert_eq!(share_value.assets, vec![coin(4000,"asset1"), coin(4000,"asset2")]);
+
+    // 11000 * (1 - (700 / 1100) ^ 2) = 6545.45, less the fee on the half that comes from asset2
+    let simulation: SimulateWithdrawResponse = app.wrap().query_wasm_smart(addr.clone(), &SimulateWithdraw {
+        lp_amount: Uint128::new(400),
+        asset: Some("asset1".to_string()),
+    }).unwrap();
+    assert_eq!(simulation.assets, vec![coin(6535,"asset1")]);
+
+    app.execute_contract(
+        holder.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance { spender: addr.to_string(), amount: Uint128::new(400), expires: None },
+        &[]
+    ).unwrap();
+
+    app.execute_contract(
+        holder.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw {
+            amount_to_burn: Uint128::new(400),
+            asset: None,
+            position_id: None,
+        },
+        &[]
+    ).unwrap();
+
+    // The withdrawal pays out the share value
+    let balances = app.wrap().query_all_balances(holder.clone()).unwrap();
+    assert_eq!(balances, share_value.assets);
+
+    let lp_balance: BalanceResponse = app.wrap().query_wasm_smart(lp_token, &Cw20QueryMsg::Balance { address: holder.to_string() }).unwrap();
+    assert_eq!(lp_balance.balance, Uint128::zero());
+}
+
+#[test]
+fn swap_and_deposit_with_reserves_past_u64() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+
+    // 1e20 tokens with 18 decimals
+    let reserve: u128 = 100_000_000_000_000_000_000_000_000_000_000_000_000;
+    for user in [&owner, &depositor] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(reserve * 2,"asset1"), coin(reserve * 2,"asset2")],
+        }))
+            .unwrap();
+    }
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(reserve,"asset1"), coin(reserve,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(reserve,"asset1"), coin(reserve,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    // k = 1e76 doesn't fit in Uint128
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(reserve / 1000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(reserve / 1000, "asset1")]
+    ).unwrap();
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves[0].amount.u128(), reserve + reserve / 1000);
+
+    // 1e38 * (1 - 1 / (1 + 0.000997)) = 9.96e34 goes out
+    let received = app.wrap().query_balance(depositor.clone(), "asset2").unwrap().amount.u128() - reserve * 2;
+    assert_eq!(received / 1_000_000_000_000_000_000_000_000_000_000, 99_600);
+
+    // Deposits of the same size as the reserves pass the ratio check
+    let deposit = pool_reserves.reserves.clone();
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: deposit.clone(),
+        },
+        &deposit
+    ).unwrap();
+
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: depositor }).unwrap();
+    assert_eq!(user_share.user_share, Uint128::new(100));
+}
+
+#[test]
+fn arithmetic_failures_return_typed_errors() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    // No LP supply to price deposits and withdrawals against
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::zero(),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    let deposit = vec![coin(1000,"asset1"), coin(1000,"asset2")];
+    let err = app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit { assets: deposit.clone() },
+        &deposit,
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InsufficientLiquidity {})));
+
+    let err = app.wrap().query_wasm_smart::<SimulateWithdrawResponse>(addr, &SimulateWithdraw { lp_amount: Uint128::zero(), asset: None }).unwrap_err();
+    assert!(err.to_string().contains("Insufficient liquidity"));
+
+    // Errors of the pool math keep their variant
+    assert!(matches!(ContractError::from(StdError::divide_by_zero(DivideByZeroError::new(1))), ContractError::DivideByZero {}));
+    assert!(matches!(ContractError::from(Uint128::zero().checked_sub(Uint128::one()).unwrap_err()), ContractError::Underflow {}));
+}
+
+#[test]
+fn position_pnl_tracks_cost_basis() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+
+    for user in [&owner, &depositor] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+        }))
+            .unwrap();
+    }
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(1000,"asset1"), coin(1000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(1000,"asset1"), coin(1000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(10000,"asset1"), coin(10000,"asset2")],
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+
+    // Right after the deposit the position is worth what was deposited
+    let pnl: PositionPnlResponse = app.wrap().query_wasm_smart(addr.clone(), &PositionPnl { user: depositor.clone() }).unwrap();
+    assert_eq!(pnl.lp_amount, Uint128::new(1000));
+    assert_eq!(pnl.deposits, vec![coin(10000,"asset1"), coin(10000,"asset2")]);
+    assert_eq!(pnl.entry_price, Decimal256::one());
+    assert_eq!(pnl.current_value, Uint128::new(20000));
+    assert_eq!(pnl.hold_value, Uint128::new(20000));
+    assert_eq!(pnl.impermanent_loss, Decimal256::zero());
+    assert_eq!(pnl.fees_earned, Uint128::zero());
+
+    // The swap of 11000 asset1 (fee 33) moves the reserves to 22000 / 5509, so the asset1 price falls to about 0.25
+    app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(11000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(11000,"asset1")]
+    ).unwrap();
+
+    // The LP tokens are worth 20000 asset1 and 5008 asset2, the deposit 10000 of each.
+    // Against holding the position lost 1 - 2 * sqrt(0.2504) / 1.2504 = 19.96%, and earned
+    // about 1000 / 1100 of the 33 asset1 fee
+    let pnl: PositionPnlResponse = app.wrap().query_wasm_smart(addr.clone(), &PositionPnl { user: depositor.clone() }).unwrap();
+    assert_eq!(pnl.current_price, Decimal256::from_ratio(5509u128, 22000u128));
+    assert_eq!(pnl.current_value, Uint128::new(20000 * 5509 / 22000 + 5008));
+    assert_eq!(pnl.hold_value, Uint128::new(10000 * 5509 / 22000 + 10000));
+    assert_eq!(pnl.impermanent_loss.to_string(), "19.9607668676350453");
+    assert_eq!(pnl.fees_earned, Uint128::new(8));
+
+    // Withdrawing half the LP tokens takes half the deposits out of the cost basis
+    app.execute_contract(
+        depositor.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance { spender: addr.to_string(), amount: Uint128::new(500), expires: None },
+        &[]
+    ).unwrap();
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw {
+            amount_to_burn: Uint128::new(500),
+            asset: None,
+            position_id: None,
+        },
+        &[]
+    ).unwrap();
+
+    // Depositing again at the new price averages the entry price by LP tokens
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(12000,"asset1"), coin(3005,"asset2")],
+        },
+        &[coin(12000,"asset1"), coin(3005,"asset2")]
+    ).unwrap();
+
+    // (500 * 1 + 600 * 0.2504) / 1100 = 0.5911, and the deposits are 5000 + 12000 asset1 and 5000 + 3005 asset2
+    let pnl: PositionPnlResponse = app.wrap().query_wasm_smart(addr.clone(), &PositionPnl { user: depositor.clone() }).unwrap();
+    assert_eq!(pnl.lp_amount, Uint128::new(1100));
+    assert_eq!(pnl.deposits, vec![coin(17000,"asset1"), coin(8005,"asset2")]);
+    assert_eq!(pnl.entry_price.to_string(), "0.591136363636363635");
+    assert!(pnl.current_value < pnl.hold_value);
+
+    // Addresses without deposits have no cost basis
+    let pnl: PositionPnlResponse = app.wrap().query_wasm_smart(addr, &PositionPnl { user: Addr::unchecked("stranger") }).unwrap();
+    assert_eq!(pnl.lp_amount, Uint128::zero());
+    assert_eq!(pnl.current_value, Uint128::zero());
+}
+
+#[test]
+fn pool_stats_track_swaps_and_liquidity() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::percent(100),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+    app.execute_contract(
+        owner.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance { spender: addr.to_string(), amount: Uint128::new(10), expires: None },
+        &[]
+    ).unwrap();
+
+    let swap = |app: &mut App, offered_asset: Coin| {
+        app.execute_contract(owner.clone(), addr.clone(), &ExecuteMsg::Swap { offered_asset: offered_asset.clone(), ask_denom: None, referral_address: None, referral_commission: None }, &[offered_asset]).unwrap();
+    };
+
+    app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit { assets: vec![coin(10000,"asset1"), coin(10000,"asset2")] },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+    app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw { amount_to_burn: Uint128::new(10), asset: None, position_id: None },
+        &[]
+    ).unwrap();
+
+    // The fees of 0.3, 0.6 and 0.15 round up to 1 each
+    swap(&mut app, coin(100, "asset1"));
+    swap(&mut app, coin(200, "asset1"));
+    let first_hour = app.block_info().time.seconds() / 3600 * 3600;
+    app.update_block(|block| block.time = block.time.plus_seconds(3600));
+    swap(&mut app, coin(50, "asset2"));
+
+    let stats: PoolStatsResponse = app.wrap().query_wasm_smart(addr.clone(), &PoolStats {}).unwrap();
+    assert_eq!(stats.volume, vec![coin(300,"asset1"), coin(50,"asset2")]);
+    assert_eq!(stats.fees_collected, vec![coin(2,"asset1"), coin(1,"asset2")]);
+    assert_eq!((stats.swap_count, stats.deposit_count, stats.withdraw_count), (3, 1, 1));
+    assert_eq!(stats.volume_24h, vec![coin(300,"asset1"), coin(50,"asset2")]);
+    assert_eq!(stats.hourly_volume, vec![
+        HourlyVolume { start_time: first_hour, volume: vec![coin(300,"asset1"), coin(0,"asset2")] },
+        HourlyVolume { start_time: first_hour + 3600, volume: vec![coin(0,"asset1"), coin(50,"asset2")] },
+    ]);
+
+    // A day later the first hour has left the rolling volume
+    app.update_block(|block| block.time = block.time.plus_seconds(23 * 3600));
+    let stats: PoolStatsResponse = app.wrap().query_wasm_smart(addr.clone(), &PoolStats {}).unwrap();
+    assert_eq!(stats.volume_24h, vec![coin(0,"asset1"), coin(50,"asset2")]);
+
+    swap(&mut app, coin(10, "asset1"));
+    let stats: PoolStatsResponse = app.wrap().query_wasm_smart(addr, &PoolStats {}).unwrap();
+    assert_eq!(stats.volume, vec![coin(310,"asset1"), coin(50,"asset2")]);
+    assert_eq!(stats.swap_count, 4);
+    assert_eq!(stats.volume_24h, vec![coin(10,"asset1"), coin(50,"asset2")]);
+    assert_eq!(stats.hourly_volume.len(), 2);
+}
+
+// Attributes of the first event of a type, without the contract address
+fn event_attributes(res: &AppResponse, ty: &str) -> Vec<(String, String)> {
+    res.events
+        .iter()
+        .find(|event| event.ty == ty)
+        .unwrap()
+        .attributes
+        .iter()
+        .filter(|attribute| attribute.key != "_contract_address")
+        .map(|attribute| (attribute.key.clone(), attribute.value.clone()))
+        .collect()
+}
+
+fn attributes(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+    pairs.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect()
+}
+
+#[test]
+fn swap_deposit_and_withdraw_emit_typed_events() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_lp_token(&mut app);
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    // Same swap as in successful_swap
+    let res = app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap { offered_asset: coin(100, "asset1"), ask_denom: None, referral_address: None, referral_commission: None },
+        &[coin(100,"asset1")]
+    ).unwrap();
+    assert_eq!(event_attributes(&res, "wasm-swap"), attributes(&[
+        ("sender", "owner"),
+        ("receiver", "owner"),
+        ("offer_asset", "asset1"),
+        ("offer_amount", "100"),
+        ("ask_asset", "asset2"),
+        ("ask_amount", "98"),
+        ("fee", "1"),
+        ("protocol_fee", "0"),
+        ("reserves", "100100asset1,99902asset2"),
+    ]));
+
+    // 100 * 10010 / 100100 = 10 LP tokens
+    let res = app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit { assets: vec![coin(10010,"asset1"), coin(9991,"asset2")] },
+        &[coin(10010,"asset1"), coin(9991,"asset2")]
+    ).unwrap();
+    assert_eq!(event_attributes(&res, "wasm-deposit"), attributes(&[
+        ("sender", "owner"),
+        ("receiver", "owner"),
+        ("assets", "10010asset1,9991asset2"),
+        ("lp_amount", "10"),
+        ("reserves", "110110asset1,109893asset2"),
+    ]));
+
+    app.execute_contract(
+        owner.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance { spender: addr.to_string(), amount: Uint128::new(10), expires: None },
+        &[]
+    ).unwrap();
+    // 110110 * 10 / 110 = 10010 and 109893 * 10 / 110 = 9990.3
+    let res = app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw { amount_to_burn: Uint128::new(10), asset: None, position_id: None },
+        &[]
+    ).unwrap();
+    assert_eq!(event_attributes(&res, "wasm-withdraw"), attributes(&[
+        ("sender", "owner"),
+        ("receiver", "owner"),
+        ("assets", "10010asset1,9990asset2"),
+        ("lp_amount", "10"),
+        ("reserves", "100100asset1,99903asset2"),
+    ]));
+}
+
+#[test]
+fn dynamic_fee_follows_price_volatility() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2"), coin(100000,"asset3")],
+    }))
+        .unwrap();
+
+    let config = |dynamic_fee: DynamicFee| Config {
+        lp_token_addr: "lp_tokens".to_string(),
+        fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+        tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+        pool_type: PoolType::ConstantProduct,
+        position_nft_addr: None,
+        dynamic_fee: Some(dynamic_fee),
+        max_referral_commission: Decimal::zero(),
+    };
+    let dynamic_fee = DynamicFee {
+        min_fee: Decimal::permille(1),
+        max_fee: Decimal::percent(1),
+        window: 3600,
+        max_volatility: Decimal::percent(50),
+    };
+
+    // The min fee can't be above the max fee
+    let err = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: config(DynamicFee { min_fee: Decimal::percent(2), ..dynamic_fee.clone() }),
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidDynamicFee {})));
+
+    // The fee only follows the price of a single pair
+    let err = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2"), coin(100000,"asset3")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: config(dynamic_fee.clone()),
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2"), coin(100000,"asset3")],
+        "mock-amm-contract",
+        None,
+    ).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidDynamicFee {})));
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: config(dynamic_fee),
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let simulate = |app: &App| -> SimulateSwapResponse {
+        app.wrap().query_wasm_smart(addr.clone(), &SimulateSwap { offered_asset: coin(1000, "asset1"), ask_denom: None }).unwrap()
+    };
+
+    // Without price moves the fee is the min fee
+    let simulation = simulate(&app);
+    assert_eq!(simulation.fee_share, Decimal::permille(1));
+    assert_eq!(simulation.fee_amount, Uint128::new(1));
+
+    // A swap of 10000 asset1 at the min fee pays 100000 - 10^10 / 109990 = 9082.6 asset2, moving the price
+    // from 1 to 90918 / 110000 = 0.8265, a volatility of 0.1735 / 0.8265 = 20.99%
+    app.execute_contract(
+        owner.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap { offered_asset: coin(10000, "asset1"), ask_denom: None, referral_address: None, referral_commission: None },
+        &[coin(10000,"asset1")]
+    ).unwrap();
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(110000,"asset1"), coin(90918,"asset2")]);
+
+    // 0.1% + 0.9% * 20.99% / 50% = 0.478%
+    let simulation = simulate(&app);
+    assert_eq!(simulation.fee_share.to_string(), "0.004777865769154622");
+    assert_eq!(simulation.fee_amount, Uint128::new(5));
+    let volatile_fee_share = simulation.fee_share;
+
+    // Dust swaps don't push the earlier prices out of the window
+    for _ in 0..100 {
+        app.execute_contract(
+            owner.clone(),
+            addr.clone(),
+            &ExecuteMsg::Swap { offered_asset: coin(20, "asset1"), ask_denom: None, referral_address: None, referral_commission: None },
+            &[coin(20,"asset1")]
+        ).unwrap();
+    }
+    let simulation = simulate(&app);
+    assert!(simulation.fee_share >= volatile_fee_share);
+
+    // Once the swap prices leave the window the fee is back to the min fee
+    app.update_block(|block| block.time = block.time.plus_seconds(3601));
+    let simulation = simulate(&app);
+    assert_eq!(simulation.fee_share, Decimal::permille(1));
+}
+
+#[test]
+fn fee_discounts_lower_the_swap_fee() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let market_maker = Addr::unchecked("market_maker");
+
+    for user in [&owner, &market_maker] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+        }))
+            .unwrap();
+    }
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let set_fee_discount = |app: &mut App, sender: &Addr, discount: Option<Decimal>| {
+        app.execute_contract(sender.clone(), addr.clone(), &ExecuteMsg::SetFeeDiscount { address: market_maker.to_string(), discount }, &[])
+    };
+
+    // Only the owner manages the discounts, and a discount can't make the fee negative
+    let err = set_fee_discount(&mut app, &market_maker, Some(Decimal::percent(50))).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::Unauthorized {})));
+    let err = set_fee_discount(&mut app, &owner, Some(Decimal::percent(150))).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidFeeDiscount {})));
+
+    set_fee_discount(&mut app, &owner, Some(Decimal::percent(50))).unwrap();
+    let effective_fee: EffectiveFeeResponse = app.wrap().query_wasm_smart(addr.clone(), &EffectiveFee { address: market_maker.clone() }).unwrap();
+    assert_eq!(effective_fee, EffectiveFeeResponse {
+        address: market_maker.clone(),
+        fee_share: Decimal::permille(3),
+        discount: Decimal::percent(50),
+        effective_fee_share: Decimal::from_ratio(15u128, 10000u128),
+    });
+
+    // The fee of 1000 * 0.0015 = 1.5 rounds up to 2, and the output of 100000 - 10^10 / 100998 = 988.1 rounds down
+    let res = app.execute_contract(
+        market_maker.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap { offered_asset: coin(1000, "asset1"), ask_denom: None, referral_address: None, referral_commission: None },
+        &[coin(1000,"asset1")]
+    ).unwrap();
+    let swap_event = event_attributes(&res, "wasm-swap");
+    assert!(swap_event.contains(&("fee".to_string(), "2".to_string())));
+    assert!(swap_event.contains(&("ask_amount".to_string(), "988".to_string())));
+
+    // Other addresses pay the whole fee
+    let effective_fee: EffectiveFeeResponse = app.wrap().query_wasm_smart(addr.clone(), &EffectiveFee { address: owner.clone() }).unwrap();
+    assert_eq!(effective_fee.effective_fee_share, Decimal::permille(3));
+
+    // Removing the discount brings back the whole fee
+    set_fee_discount(&mut app, &owner, None).unwrap();
+    let effective_fee: EffectiveFeeResponse = app.wrap().query_wasm_smart(addr, &EffectiveFee { address: market_maker }).unwrap();
+    assert_eq!(effective_fee.discount, Decimal::zero());
+    assert_eq!(effective_fee.effective_fee_share, Decimal::permille(3));
+}
+
+#[test]
+fn owner_manages_discounts_and_can_be_transferred() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let creator = Addr::unchecked("creator");
+    let owner = Addr::unchecked("owner");
+    let new_owner = Addr::unchecked("new_owner");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: creator.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let addr = app.instantiate_contract(
+        code_id,
+        creator.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: Some(owner.to_string()),
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let set_fee_discount = |app: &mut App, sender: &Addr| {
+        app.execute_contract(sender.clone(), addr.clone(), &ExecuteMsg::SetFeeDiscount { address: "market_maker".to_string(), discount: Some(Decimal::percent(50)) }, &[])
+    };
+    let update_owner = |app: &mut App, sender: &Addr| {
+        app.execute_contract(sender.clone(), addr.clone(), &ExecuteMsg::UpdateOwner { owner: new_owner.to_string() }, &[])
+    };
+
+    // The owner given at instantiation manages the pool, not the instantiator
+    let response: OwnerResponse = app.wrap().query_wasm_smart(addr.clone(), &Owner {}).unwrap();
+    assert_eq!(response.owner, Some(owner.clone()));
+    let err = set_fee_discount(&mut app, &creator).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::Unauthorized {})));
+    set_fee_discount(&mut app, &owner).unwrap();
+
+    // Only the owner hands the ownership over
+    let err = update_owner(&mut app, &creator).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::Unauthorized {})));
+    update_owner(&mut app, &owner).unwrap();
+
+    let response: OwnerResponse = app.wrap().query_wasm_smart(addr.clone(), &Owner {}).unwrap();
+    assert_eq!(response.owner, Some(new_owner.clone()));
+    let err = set_fee_discount(&mut app, &owner).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::Unauthorized {})));
+    set_fee_discount(&mut app, &new_owner).unwrap();
+}
+
+#[test]
+fn swap_pays_referral_commission() {
+    let mut app = App::default();
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    let owner = Addr::unchecked("owner");
+    let frontend = Addr::unchecked("frontend");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let addr = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::percent(1),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let swap = |app: &mut App, referral_address: Option<&Addr>, referral_commission: Option<Decimal>| {
+        app.execute_contract(
+            owner.clone(),
+            addr.clone(),
+            &ExecuteMsg::Swap {
+                offered_asset: coin(10000, "asset1"),
+                ask_denom: None,
+                referral_address: referral_address.map(|address| address.to_string()),
+                referral_commission,
+            },
+            &[coin(10000,"asset1")]
+        )
+    };
+
+    // The commission is capped by the config and needs a referral address
+    let err = swap(&mut app, Some(&frontend), Some(Decimal::percent(2))).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidReferral {})));
+    let err = swap(&mut app, None, Some(Decimal::permille(5))).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidReferral {})));
+
+    // 10000 * 0.5% = 50 asset1 go to the frontend and 9950 are swapped, paying a fee of 29.85, rounded up to 30.
+    // The output is 100000 - 10^10 / 109920 = 9024.7, rounded down
+    let res = swap(&mut app, Some(&frontend), Some(Decimal::permille(5))).unwrap();
+    assert_eq!(app.wrap().query_balance(&frontend, "asset1").unwrap().amount, Uint128::new(50));
+
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(109950,"asset1"), coin(90976,"asset2")]);
+
+    assert_eq!(event_attributes(&res, "wasm-referral"), attributes(&[
+        ("sender", "owner"),
+        ("referral_address", "frontend"),
+        ("commission_asset", "asset1"),
+        ("commission_amount", "50"),
+    ]));
+    let swap_event = event_attributes(&res, "wasm-swap");
+    assert!(swap_event.contains(&("offer_amount".to_string(), "9950".to_string())));
+    assert!(swap_event.contains(&("ask_amount".to_string(), "9024".to_string())));
+}
+
+// State of a 0.1.0 pool: the owner holds the initial 100 LP tokens and the depositor 50 shares in the ledger
+#[cw_serde]
+struct LegacyPoolReserves {
+    asset1: Coin,
+    asset2: Coin,
+}
+
+#[cw_serde]
+struct LegacyConfig {
+    lp_token_addr: String,
+    fee_share: Decimal,
+    tolerance_percentage: Decimal,
+}
+
+fn instantiate_legacy_pool(deps: DepsMut, _env: Env, info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    cw2::set_contract_version(deps.storage, "crates.io:xyk-amm", "0.1.0")?;
+    Item::new("pool_reserves").save(deps.storage, &LegacyPoolReserves { asset1: info.funds[0].clone(), asset2: info.funds[1].clone() })?;
+    Item::new("Config").save(deps.storage, &LegacyConfig {
+        lp_token_addr: "contract0".to_string(),
+        fee_share: Decimal::permille(3),
+        tolerance_percentage: Decimal::permille(3),
+    })?;
+    Item::new("total_shares").save(deps.storage, &Uint128::new(150))?;
+    Map::new("share_balances").save(deps.storage, &Addr::unchecked("depositor"), &Uint128::new(50))?;
+    Ok(Response::new())
+}
+
+#[test]
+fn migrate_converts_a_legacy_pool() {
+    let mut app = App::default();
+    let legacy_code_id = app.store_code(Box::new(ContractWrapper::new(
+        amm_contract::contract::execute,
+        instantiate_legacy_pool,
+        amm_contract::contract::query,
+    )));
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    ).with_migrate(amm_contract::contract::migrate)));
+
+    let owner = Addr::unchecked("owner");
+    let depositor = Addr::unchecked("depositor");
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let lp_token = instantiate_lp_token(&mut app);
+    let addr = app.instantiate_contract(
+        legacy_code_id,
+        owner.clone(),
+        &Empty {},
+        &[coin(150000,"asset1"), coin(150000,"asset2")],
+        "legacy-amm-contract",
+        Some(owner.to_string()),
+    ).unwrap();
+    set_lp_minter(&mut app, &lp_token, &addr);
+
+    // Swap fees the legacy pool kept out of its reserves
+    app.send_tokens(owner.clone(), addr.clone(), &[coin(300,"asset1"), coin(600,"asset2")]).unwrap();
+
+    app.migrate_contract(owner.clone(), addr.clone(), &MigrateMsg { owner: Some(owner.to_string()) }, code_id).unwrap();
+
+    // The ledger shares are now LP tokens, and the pool state loads in the current layout
+    let balance: BalanceResponse = app.wrap().query_wasm_smart(&lp_token, &Cw20QueryMsg::Balance { address: depositor.to_string() }).unwrap();
+    assert_eq!(balance.balance, Uint128::new(50));
+    let user_share: UserShareResponse = app.wrap().query_wasm_smart(addr.clone(), &GetUserShare { user: depositor.clone() }).unwrap();
+    assert_eq!(user_share.user_share, Uint128::new(50));
+
+    // The fees are added to the reserves
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(150300,"asset1"), coin(150600,"asset2")]);
+    let config: Config = app.wrap().query_wasm_smart(addr.clone(), &GetConfig {}).unwrap();
+    assert_eq!(config.pool_type, PoolType::ConstantProduct);
+    assert_eq!(config.lp_token_addr, lp_token.to_string());
+
+    // The depositor withdraws their share, a third of the pool
+    app.execute_contract(
+        depositor.clone(),
+        lp_token.clone(),
+        &Cw20ExecuteMsg::IncreaseAllowance { spender: addr.to_string(), amount: Uint128::new(50), expires: None },
+        &[],
+    ).unwrap();
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Withdraw { amount_to_burn: Uint128::new(50), asset: None, position_id: None },
+        &[],
+    ).unwrap();
+    assert_eq!(app.wrap().query_balance(&depositor, "asset1").unwrap().amount, Uint128::new(50100));
+
+    // The pool is on the current version now, it can't be migrated again to it
+    let err = app.migrate_contract(owner.clone(), addr.clone(), &MigrateMsg { owner: None }, code_id).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidMigration {})));
+}
@@ -0,0 +1,350 @@
+use cosmwasm_std::{Addr, coin, Decimal, Uint128};
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use amm_contract::msg::{Config, ExecuteMsg, InstantiateMsg, PoolType, ReservesResponse, SimulateSwapResponse};
+use amm_contract::msg::QueryMsg::{GetConcentratedPool, GetPosition, GetReserves, SimulateSwap};
+use amm_contract::state::{ConcentratedPool, Position};
+
+fn setup_concentrated_pool(app: &mut App) -> Addr {
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+
+    for user in ["owner", "depositor", "swapper"] {
+        app.sudo(SudoMsg::Bank(BankSudo::Mint {
+            to_address: user.to_string(),
+            amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+        }))
+            .unwrap();
+    }
+
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::Concentrated { tick_spacing: 10 },
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap()
+}
+
+#[test]
+fn instantiate_creates_full_range_position() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+
+    let pool: ConcentratedPool = app.wrap().query_wasm_smart(addr.clone(), &GetConcentratedPool {}).unwrap();
+    assert_eq!(pool.sqrt_price, cosmwasm_std::Decimal256::one());
+    assert_eq!(pool.current_tick, 0);
+
+    let position: Position = app.wrap().query_wasm_smart(addr.clone(), &GetPosition { owner: Addr::unchecked("owner"), position_id: 0 }).unwrap();
+    assert_eq!(position.lower_tick, -200000);
+    assert_eq!(position.upper_tick, 200000);
+    assert_eq!(position.liquidity, pool.liquidity);
+
+    // The full range position holds almost all of the initial funding
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    for reserve in pool_reserves.reserves {
+        assert!(reserve.amount <= Uint128::new(100000) && reserve.amount > Uint128::new(99000));
+    }
+}
+
+#[test]
+fn create_position_and_refund_unused_funds() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+    let depositor = Addr::unchecked("depositor");
+
+    // Ticks must be multiples of the tick spacing
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -105,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(10000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap_err();
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(20000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(20000,"asset2")]
+    ).unwrap();
+
+    let position: Position = app.wrap().query_wasm_smart(addr.clone(), &GetPosition { owner: depositor.clone(), position_id: 1 }).unwrap();
+
+    // L = 10000 * sqrt(1) * sqrt(1.0001^100) / (sqrt(1.0001^100) - sqrt(1)) = 2005104
+    assert_eq!(position.liquidity, Uint128::new(2005104));
+
+    // The price is in the middle of the range, so the position takes the same amount of each asset
+    // and the extra asset2 is sent back
+    let balance1 = app.wrap().query_balance(depositor.clone(), "asset1").unwrap();
+    let balance2 = app.wrap().query_balance(depositor.clone(), "asset2").unwrap();
+    assert_eq!(balance1.amount, Uint128::new(100000000 - 10000));
+    assert_eq!(balance2.amount, Uint128::new(100000000 - 10000));
+}
+
+#[test]
+fn swap_earns_fees_for_positions_in_range() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+    let depositor = Addr::unchecked("depositor");
+    let swapper = Addr::unchecked("swapper");
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(10000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+
+    let simulation: SimulateSwapResponse = app.wrap().query_wasm_smart(addr.clone(), &SimulateSwap {
+        offered_asset: coin(1000, "asset1"),
+        ask_denom: None,
+    }).unwrap();
+
+    app.execute_contract(
+        swapper.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(1000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(1000,"asset1")]
+    ).unwrap();
+
+    // The concentrated position makes the swap cheaper than a 100000/100000 xy=k pool (990 out)
+    let balance2 = app.wrap().query_balance(swapper.clone(), "asset2").unwrap();
+    let received = balance2.amount - Uint128::new(100000000);
+    assert!(received > Uint128::new(990) && received < Uint128::new(997));
+    assert_eq!(simulation.return_asset, coin(received.u128(), "asset2"));
+    assert_eq!(simulation.fee_amount, Uint128::new(3));
+
+    let pool: ConcentratedPool = app.wrap().query_wasm_smart(addr.clone(), &GetConcentratedPool {}).unwrap();
+    assert!(pool.current_tick < 0 && pool.current_tick > -100);
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CollectFees { position_id: 1 },
+        &[]
+    ).unwrap();
+
+    // The 3 asset1 fee is split by liquidity between the full range position and the depositor,
+    // who provides most of the liquidity around the current price
+    let balance1 = app.wrap().query_balance(depositor.clone(), "asset1").unwrap();
+    assert_eq!(balance1.amount, Uint128::new(100000000 - 10000 + 2));
+}
+
+#[test]
+fn swap_crosses_ticks_out_of_range() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+    let depositor = Addr::unchecked("depositor");
+    let swapper = Addr::unchecked("swapper");
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(10000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+
+    let initial: ConcentratedPool = app.wrap().query_wasm_smart(addr.clone(), &GetConcentratedPool {}).unwrap();
+
+    // Push the price below the depositor's range
+    app.execute_contract(
+        swapper.clone(),
+        addr.clone(),
+        &ExecuteMsg::Swap {
+            offered_asset: coin(20000, "asset1"),
+            ask_denom: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[coin(20000,"asset1")]
+    ).unwrap();
+
+    let pool: ConcentratedPool = app.wrap().query_wasm_smart(addr.clone(), &GetConcentratedPool {}).unwrap();
+    assert!(pool.current_tick < -100);
+
+    // Only the full range position is active once the price leaves the range
+    let full_range: Position = app.wrap().query_wasm_smart(addr.clone(), &GetPosition { owner: Addr::unchecked("owner"), position_id: 0 }).unwrap();
+    assert_eq!(pool.liquidity, full_range.liquidity);
+    assert!(initial.liquidity > pool.liquidity);
+
+    // Below the range the depositor's position is all asset1
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::DecreaseLiquidity { position_id: 1, liquidity: Uint128::new(2005104) },
+        &[]
+    ).unwrap();
+
+    let balance1 = app.wrap().query_balance(depositor.clone(), "asset1").unwrap();
+    let balance2 = app.wrap().query_balance(depositor.clone(), "asset2").unwrap();
+    assert!(balance1.amount > Uint128::new(100000000));
+    assert_eq!(balance2.amount, Uint128::new(100000000 - 10000));
+}
+
+#[test]
+fn decrease_liquidity_errors() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+    let depositor = Addr::unchecked("depositor");
+
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(10000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+
+    // More than the position holds
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::DecreaseLiquidity { position_id: 1, liquidity: Uint128::new(2005105) },
+        &[]
+    ).unwrap_err();
+
+    // Positions can only be changed by their owner
+    app.execute_contract(
+        Addr::unchecked("swapper"),
+        addr.clone(),
+        &ExecuteMsg::DecreaseLiquidity { position_id: 1, liquidity: Uint128::new(1) },
+        &[]
+    ).unwrap_err();
+
+    // Fungible LP deposits aren't supported in concentrated mode
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::Deposit {
+            assets: vec![coin(1000,"asset1"), coin(1000,"asset2")],
+        },
+        &[coin(1000,"asset1"), coin(1000,"asset2")]
+    ).unwrap_err();
+
+    // Withdrawing everything gives back the deposit minus rounding
+    app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::DecreaseLiquidity { position_id: 1, liquidity: Uint128::new(2005104) },
+        &[]
+    ).unwrap();
+
+    let balance1 = app.wrap().query_balance(depositor.clone(), "asset1").unwrap();
+    let balance2 = app.wrap().query_balance(depositor.clone(), "asset2").unwrap();
+    assert_eq!(balance1.amount, Uint128::new(100000000 - 1));
+    assert_eq!(balance2.amount, Uint128::new(100000000 - 1));
+}
+
+// Attributes of the first event of a type, as (key, value) pairs
+fn event_attributes(res: &AppResponse, ty: &str) -> Vec<(String, String)> {
+    res.events
+        .iter()
+        .find(|event| event.ty == ty)
+        .map(|event| event.attributes.iter().filter(|attr| attr.key != "_contract_address").map(|attr| (attr.key.clone(), attr.value.clone())).collect())
+        .unwrap_or_default()
+}
+
+fn reserves(app: &App, addr: &Addr) -> String {
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(addr.clone(), &GetReserves {}).unwrap();
+    pool_reserves.reserves.iter().map(|coin| coin.to_string()).collect::<Vec<_>>().join(",")
+}
+
+#[test]
+fn position_changes_emit_typed_events() {
+    let mut app = App::default();
+    let addr = setup_concentrated_pool(&mut app);
+    let depositor = Addr::unchecked("depositor");
+
+    let res = app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CreatePosition {
+            lower_tick: -100,
+            upper_tick: 100,
+            asset1: coin(10000,"asset1"),
+            asset2: coin(10000,"asset2"),
+        },
+        &[coin(10000,"asset1"), coin(10000,"asset2")]
+    ).unwrap();
+    let attributes: Vec<(String, String)> = [
+        ("sender", "depositor"),
+        ("receiver", "depositor"),
+        ("assets", "10000asset1,10000asset2"),
+        ("lp_amount", "2005104"),
+        ("reserves", &reserves(&app, &addr)),
+        ("position_id", "1"),
+    ].iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    assert_eq!(event_attributes(&res, "wasm-deposit"), attributes);
+
+    let res = app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::DecreaseLiquidity { position_id: 1, liquidity: Uint128::new(2005104) },
+        &[]
+    ).unwrap();
+    let withdraw_event = event_attributes(&res, "wasm-withdraw");
+    assert!(withdraw_event.contains(&("lp_amount".to_string(), "2005104".to_string())));
+    assert!(withdraw_event.contains(&("position_id".to_string(), "1".to_string())));
+    assert!(withdraw_event.contains(&("reserves".to_string(), reserves(&app, &addr))));
+
+    let res = app.execute_contract(
+        depositor.clone(),
+        addr.clone(),
+        &ExecuteMsg::CollectFees { position_id: 1 },
+        &[]
+    ).unwrap();
+    let attributes: Vec<(String, String)> = [
+        ("sender", "depositor"),
+        ("receiver", "depositor"),
+        ("position_id", "1"),
+        ("assets", "0asset1,0asset2"),
+        ("reserves", &reserves(&app, &addr)),
+    ].iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    assert_eq!(event_attributes(&res, "wasm-collect_fees"), attributes);
+}
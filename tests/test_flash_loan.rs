@@ -0,0 +1,137 @@
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Addr, BankMsg, Binary, coin, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, to_json_binary, Uint128};
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use cw_multi_test::error::AnyResult;
+use amm_contract::msg::{Config, ExecuteMsg, InstantiateMsg, PoolType, ReservesResponse};
+use amm_contract::msg::QueryMsg::GetReserves;
+
+// Borrower that sends `repay` to the pool when called back
+#[cw_serde]
+enum BorrowerMsg {
+    Repay { pool: String, repay: Vec<Coin> },
+}
+
+fn borrower_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, msg: BorrowerMsg) -> StdResult<Response> {
+    match msg {
+        BorrowerMsg::Repay { pool, repay } => Ok(Response::new().add_message(BankMsg::Send { to_address: pool, amount: repay })),
+    }
+}
+
+fn borrower_instantiate(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::new())
+}
+
+fn borrower_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_json_binary(&Empty {})
+}
+
+// Pool with 100000/100000 reserves and a borrower holding 1000 of each asset for the fees
+fn setup_flash_loan(app: &mut App) -> (Addr, Addr) {
+    let pool_code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    )
+        .with_reply(amm_contract::contract::reply);
+    let pool_code_id = app.store_code(Box::new(pool_code));
+    let borrower_code_id = app.store_code(Box::new(ContractWrapper::new(borrower_execute, borrower_instantiate, borrower_query)));
+
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "owner".to_string(),
+        amount: vec![coin(100000000,"asset1"), coin(100000000,"asset2")],
+    }))
+        .unwrap();
+
+    let pool = app.instantiate_contract(
+        pool_code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000,"asset1"), coin(100000,"asset2")],
+            initial_lp_token_supply: Uint128::new(100),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000,"asset1"), coin(100000,"asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    let borrower = app.instantiate_contract(
+        borrower_code_id,
+        Addr::unchecked("owner"),
+        &Empty {},
+        &[coin(1000,"asset1"), coin(1000,"asset2")],
+        "mock-borrower",
+        None,
+    ).unwrap();
+
+    (pool, borrower)
+}
+
+fn flash_loan(app: &mut App, pool: &Addr, borrower: &Addr, assets: Vec<Coin>, repay: Vec<Coin>) -> AnyResult<AppResponse> {
+    app.execute_contract(
+        borrower.clone(),
+        pool.clone(),
+        &ExecuteMsg::FlashLoan {
+            assets,
+            callback_msg: to_json_binary(&BorrowerMsg::Repay { pool: pool.to_string(), repay }).unwrap(),
+        },
+        &[],
+    )
+}
+
+#[test]
+fn flash_loan_repaid_with_fee() {
+    let mut app = App::default();
+    let (pool, borrower) = setup_flash_loan(&mut app);
+
+    // 50000 * 0.003 = 150 asset1 fee, 10000 * 0.003 = 30 asset2 fee
+    let res = flash_loan(
+        &mut app,
+        &pool,
+        &borrower,
+        vec![coin(50000,"asset1"), coin(10000,"asset2")],
+        vec![coin(50150,"asset1"), coin(10030,"asset2")],
+    ).unwrap();
+
+    // The fee is added to the reserves
+    let pool_reserves: ReservesResponse = app.wrap().query_wasm_smart(pool.clone(), &GetReserves {}).unwrap();
+    assert_eq!(pool_reserves.reserves, vec![coin(100150,"asset1"), coin(100030,"asset2")]);
+
+    // The repayment emits the new reserves
+    let event = res.events.iter().find(|event| event.ty == "wasm-reserves").unwrap();
+    assert!(event.attributes.iter().any(|attr| attr.key == "action" && attr.value == "flash_loan_repaid"));
+    assert!(event.attributes.iter().any(|attr| attr.key == "reserves" && attr.value == "100150asset1,100030asset2"));
+
+    let balance = app.wrap().query_balance(borrower, "asset1").unwrap();
+    assert_eq!(balance.amount, Uint128::new(1000 - 150));
+}
+
+#[test]
+fn flash_loan_reverts_without_fee() {
+    let mut app = App::default();
+    let (pool, borrower) = setup_flash_loan(&mut app);
+
+    // Paying back only the principal reverts the loan
+    assert!(flash_loan(&mut app, &pool, &borrower, vec![coin(50000,"asset1")], vec![coin(50000,"asset1")]).is_err());
+
+    // Nothing moved
+    let balance = app.wrap().query_balance(pool.clone(), "asset1").unwrap();
+    assert_eq!(balance.amount, Uint128::new(100000));
+    let balance = app.wrap().query_balance(borrower.clone(), "asset1").unwrap();
+    assert_eq!(balance.amount, Uint128::new(1000));
+
+    // More than the reserves can't be borrowed
+    assert!(flash_loan(&mut app, &pool, &borrower, vec![coin(100001,"asset1")], vec![coin(100302,"asset1")]).is_err());
+
+    // Only pool assets can be borrowed
+    assert!(flash_loan(&mut app, &pool, &borrower, vec![coin(1,"asset3")], vec![]).is_err());
+}
@@ -0,0 +1,110 @@
+use cosmwasm_std::{Decimal, Decimal256, Uint128};
+use amm_contract::curve::{ConstantProduct, PoolCurve, Weighted};
+
+#[test]
+fn constant_product_swap_in_and_out() {
+    let curve = ConstantProduct;
+    let reserves = [Uint128::new(100000), Uint128::new(100000)];
+
+    // 100000 - 10^10 / 101000 = 990.1, rounded down
+    let out = curve.swap_out(&reserves, 0, 1, Uint128::new(1000)).unwrap();
+    assert_eq!(out, Uint128::new(990));
+
+    // 100000 * 990 / (100000 - 990) = 999.9, rounded up
+    let offer = curve.swap_in(&reserves, 0, 1, out).unwrap();
+    assert_eq!(offer, Uint128::new(1000));
+
+    // The whole reserve can't be bought
+    curve.swap_in(&reserves, 0, 1, Uint128::new(100000)).unwrap_err();
+
+    assert_eq!(
+        curve.spot_price(&[Uint128::new(1500), Uint128::new(3000)], 0, 1).unwrap(),
+        Decimal256::from_ratio(2u128, 1u128)
+    );
+}
+
+#[test]
+fn weighted_swap_in_and_out() {
+    let curve = Weighted { weights: vec![Decimal::percent(80), Decimal::percent(20)] };
+    let reserves = [Uint128::new(80000), Uint128::new(20000)];
+
+    // Same price for both assets
+    assert_eq!(curve.spot_price(&reserves, 0, 1).unwrap(), Decimal256::one());
+
+    // 20000 * (1 - (80000 / 80997) ^ 4) = 966.69
+    let out = curve.swap_out(&reserves, 0, 1, Uint128::new(997)).unwrap();
+    assert_eq!(out, Uint128::new(966));
+
+    // 80000 * ((20000 / 19034) ^ 0.25 - 1) = 996.26
+    let offer = curve.swap_in(&reserves, 0, 1, out).unwrap();
+    assert_eq!(offer, Uint128::new(997));
+
+    // The counter deposit matches the 80/20 value split
+    assert_eq!(curve.counter_deposit(&reserves, 0, Uint128::new(8000), 1).unwrap(), Uint128::new(2000));
+}
+
+#[test]
+fn proportional_deposit_and_withdraw() {
+    let curve = ConstantProduct;
+    let reserves = [Uint128::new(1500), Uint128::new(3000)];
+
+    // 100 * min(200 / 1500, 500 / 3000) = 13
+    let lp = curve.lp_for_deposit(&reserves, &[Uint128::new(200), Uint128::new(500)], Uint128::new(100)).unwrap();
+    assert_eq!(lp, Uint128::new(13));
+
+    let assets = curve.assets_for_withdraw(&reserves, Uint128::new(13), Uint128::new(100)).unwrap();
+    assert_eq!(assets, vec![Uint128::new(195), Uint128::new(390)]);
+}
+
+#[test]
+fn single_asset_withdraw() {
+    let curve = ConstantProduct;
+    let reserves = [Uint128::new(100000), Uint128::new(100000), Uint128::new(100000)];
+
+    // 100000 * (1 - (90 / 100) ^ 3) = 27100, less the fee on the two thirds that come from the other assets
+    let amount = curve.asset_for_single_withdraw(&reserves, 0, Uint128::new(10), Uint128::new(100), Decimal::permille(3)).unwrap();
+    assert_eq!(amount, Uint128::new(27045));
+
+    // Without a fee, 100 * ((133200 / 100000) ^ (1/3) - 1) = 10.03
+    let lp = curve.lp_for_unbalanced_deposit(&reserves, &[Uint128::new(33200), Uint128::zero(), Uint128::zero()], Uint128::new(100), Decimal::zero()).unwrap();
+    assert_eq!(lp, Uint128::new(10));
+}
+
+#[test]
+fn constant_product_near_uint128_max() {
+    let curve = ConstantProduct;
+
+    // k is about 2.9e76, far past Uint128
+    let reserve = Uint128::new(u128::MAX / 4 * 2);
+    let reserves = [reserve, reserve];
+
+    let offer = Uint128::new(1_000_000_000_000_000_000);
+    let out = curve.swap_out(&reserves, 0, 1, offer).unwrap();
+    assert!(out <= offer && out >= offer - Uint128::new(1));
+    let offer_needed = curve.swap_in(&reserves, 0, 1, out).unwrap();
+    assert!(offer_needed >= offer - Uint128::new(1) && offer_needed <= offer + Uint128::new(1));
+
+    // The whole supply can be swapped in without overflowing
+    let out = curve.swap_out(&[Uint128::MAX, Uint128::MAX], 0, 1, Uint128::MAX).unwrap();
+    assert_eq!(out, Uint128::MAX / Uint128::new(2));
+
+    assert_eq!(curve.spot_price(&reserves, 0, 1).unwrap(), Decimal256::one());
+    assert_eq!(curve.counter_deposit(&reserves, 0, reserve, 1).unwrap(), reserve);
+
+    // 1000 * (reserve / 2) / reserve = 500
+    let lp = curve.lp_for_deposit(&reserves, &[reserve / Uint128::new(2), reserve / Uint128::new(2)], Uint128::new(1000)).unwrap();
+    assert_eq!(lp, Uint128::new(500));
+    let assets = curve.assets_for_withdraw(&reserves, Uint128::new(500), Uint128::new(1000)).unwrap();
+    assert_eq!(assets, vec![reserve / Uint128::new(2); 2]);
+}
+
+#[test]
+fn weighted_near_uint128_max() {
+    let curve = Weighted { weights: vec![Decimal::percent(80), Decimal::percent(20)] };
+    let reserves = [Uint128::MAX / Uint128::new(2), Uint128::MAX / Uint128::new(8)];
+
+    let offer = Uint128::new(1_000_000_000_000_000_000_000);
+    let out = curve.swap_out(&reserves, 0, 1, offer).unwrap();
+    let offer_needed = curve.swap_in(&reserves, 0, 1, out).unwrap();
+    assert!(offer_needed <= offer);
+}
@@ -0,0 +1,158 @@
+use cosmwasm_std::{Addr, Binary, coin, Decimal, Decimal256, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Uint128};
+use cw721::{Cw721ExecuteMsg, Cw721QueryMsg, NftInfoResponse, OwnerOfResponse};
+use cw721_base::Cw721Contract;
+use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use cw_multi_test::error::AnyResult;
+use amm_contract::error::ContractError;
+use amm_contract::msg::{Config, ExecuteMsg, InstantiateMsg, PoolType, PositionMetadata, ReservesResponse};
+use amm_contract::msg::QueryMsg::{GetReserves, PositionNft};
+
+type PositionNftContract<'a> = Cw721Contract<'a, PositionMetadata, Empty, Empty, Empty>;
+
+fn nft_execute(deps: DepsMut, env: Env, info: MessageInfo, msg: cw721_base::ExecuteMsg<PositionMetadata, Empty>) -> Result<Response, cw721_base::ContractError> {
+    PositionNftContract::default().execute(deps, env, info, msg)
+}
+
+fn nft_instantiate(deps: DepsMut, env: Env, info: MessageInfo, msg: cw721_base::InstantiateMsg) -> StdResult<Response> {
+    PositionNftContract::default().instantiate(deps, env, info, msg)
+}
+
+fn nft_query(deps: Deps, env: Env, msg: cw721_base::QueryMsg<Empty>) -> StdResult<Binary> {
+    PositionNftContract::default().query(deps, env, msg)
+}
+
+// Constant product pool with 100000/100000 reserves minting position NFTs, and alice holding 10000 of each asset
+fn setup_pool(app: &mut App) -> (Addr, Addr) {
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "owner".to_string(),
+        amount: vec![coin(100000, "asset1"), coin(100000, "asset2")],
+    })).unwrap();
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: "alice".to_string(),
+        amount: vec![coin(10000, "asset1"), coin(10000, "asset2")],
+    })).unwrap();
+
+    let nft_code_id = app.store_code(Box::new(ContractWrapper::new(nft_execute, nft_instantiate, nft_query)));
+    let pool_code_id = app.store_code(Box::new(ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    )));
+
+    // The pool is instantiated right after the NFT contract, so it gets the next contract address
+    let nft = app.instantiate_contract(
+        nft_code_id,
+        Addr::unchecked("owner"),
+        &cw721_base::InstantiateMsg {
+            name: "AMM positions".to_string(),
+            symbol: "AMMPOS".to_string(),
+            minter: "contract1".to_string(),
+        },
+        &[],
+        "mock-position-nft",
+        None,
+    ).unwrap();
+
+    let pool = app.instantiate_contract(
+        pool_code_id,
+        Addr::unchecked("owner"),
+        &InstantiateMsg {
+            initial_funding: vec![coin(100000, "asset1"), coin(100000, "asset2")],
+            initial_lp_token_supply: Uint128::new(100000),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: Some(nft.to_string()),
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(100000, "asset1"), coin(100000, "asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+    assert_eq!(pool, Addr::unchecked("contract1"));
+
+    (pool, nft)
+}
+
+fn deposit(app: &mut App, pool: &Addr) -> String {
+    let assets = vec![coin(10000, "asset1"), coin(10000, "asset2")];
+    let res = app.execute_contract(Addr::unchecked("alice"), pool.clone(), &ExecuteMsg::Deposit { assets: assets.clone() }, &assets).unwrap();
+    res.events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.key == "position_id")
+        .unwrap()
+        .value
+        .clone()
+}
+
+fn withdraw(app: &mut App, pool: &Addr, sender: &str, amount_to_burn: u128, position_id: &str) -> AnyResult<AppResponse> {
+    app.execute_contract(Addr::unchecked(sender), pool.clone(), &ExecuteMsg::Withdraw {
+        amount_to_burn: Uint128::new(amount_to_burn),
+        asset: None,
+        position_id: Some(position_id.to_string()),
+    }, &[])
+}
+
+#[test]
+fn deposit_mints_position_nft() {
+    let mut app = App::default();
+    let (pool, nft) = setup_pool(&mut app);
+
+    let position_id = deposit(&mut app, &pool);
+
+    // 10000 / 100000 of the reserves mints 10000 LP shares, recorded by the NFT instead of LP tokens
+    let expected = PositionMetadata {
+        lp_amount: Uint128::new(10000),
+        deposits: vec![coin(10000, "asset1"), coin(10000, "asset2")],
+        entry_price: Decimal256::one(),
+        timestamp: app.block_info().time.seconds(),
+    };
+    let metadata: PositionMetadata = app.wrap().query_wasm_smart(pool.clone(), &PositionNft { position_id: position_id.clone() }).unwrap();
+    assert_eq!(metadata, expected);
+
+    let owner: OwnerOfResponse = app.wrap().query_wasm_smart(nft.clone(), &Cw721QueryMsg::OwnerOf { token_id: position_id.clone(), include_expired: None }).unwrap();
+    assert_eq!(owner.owner, "alice");
+    let nft_info: NftInfoResponse<PositionMetadata> = app.wrap().query_wasm_smart(nft, &Cw721QueryMsg::NftInfo { token_id: position_id }).unwrap();
+    assert_eq!(nft_info.extension, expected);
+
+    let reserves: ReservesResponse = app.wrap().query_wasm_smart(pool, &GetReserves {}).unwrap();
+    assert_eq!(reserves.reserves, vec![coin(110000, "asset1"), coin(110000, "asset2")]);
+}
+
+#[test]
+fn withdraw_burns_position_nft() {
+    let mut app = App::default();
+    let (pool, nft) = setup_pool(&mut app);
+    let position_id = deposit(&mut app, &pool);
+
+    // Only the owner can withdraw a position
+    let err = withdraw(&mut app, &pool, "bob", 10000, &position_id).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::Unauthorized {})));
+
+    // A position is withdrawn all at once
+    let err = withdraw(&mut app, &pool, "alice", 5000, &position_id).unwrap_err();
+    assert!(matches!(err.downcast_ref::<ContractError>(), Some(ContractError::InvalidPositionAmount {})));
+
+    // The pool burns the NFT, so it needs an approval
+    assert!(withdraw(&mut app, &pool, "alice", 10000, &position_id).is_err());
+    app.execute_contract(Addr::unchecked("alice"), nft.clone(), &Cw721ExecuteMsg::Approve {
+        spender: pool.to_string(),
+        token_id: position_id.clone(),
+        expires: None,
+    }, &[]).unwrap();
+    withdraw(&mut app, &pool, "alice", 10000, &position_id).unwrap();
+
+    assert_eq!(app.wrap().query_balance("alice", "asset1").unwrap().amount, Uint128::new(10000));
+    assert_eq!(app.wrap().query_balance("alice", "asset2").unwrap().amount, Uint128::new(10000));
+
+    let owner: StdResult<OwnerOfResponse> = app.wrap().query_wasm_smart(nft, &Cw721QueryMsg::OwnerOf { token_id: position_id.clone(), include_expired: None });
+    assert!(owner.is_err());
+    let metadata: StdResult<PositionMetadata> = app.wrap().query_wasm_smart(pool, &PositionNft { position_id });
+    assert!(metadata.is_err());
+}
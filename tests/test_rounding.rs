@@ -0,0 +1,237 @@
+use cosmwasm_std::{Addr, coin, Decimal, Uint128, Uint512};
+use cw20::{Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse, TokenInfoResponse};
+use cw_multi_test::{App, BankSudo, ContractWrapper, Executor, SudoMsg};
+use proptest::prelude::*;
+use amm_contract::concentrated::{asset1_delta, asset2_delta, sqrt_price_at_tick};
+use amm_contract::msg::{Config, ExecuteMsg, InstantiateMsg, PoolType, ReservesResponse};
+use amm_contract::msg::QueryMsg::{GetConcentratedPool, GetPosition, GetReserves};
+use amm_contract::state::{ConcentratedPool, Position};
+
+const DENOMS: [&str; 2] = ["asset1", "asset2"];
+
+#[derive(Clone, Debug)]
+enum Operation {
+    Swap { offer: usize, amount: u128 },
+    Deposit { amounts: [u128; 2] },
+    SingleDeposit { asset: usize, amount: u128 },
+    Withdraw { share: u128, asset: Option<usize> },
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        (0..2usize, 1..500_000u128).prop_map(|(offer, amount)| Operation::Swap { offer, amount }),
+        (1..500_000u128, 1..500_000u128).prop_map(|(a, b)| Operation::Deposit { amounts: [a, b] }),
+        (0..2usize, 1..500_000u128).prop_map(|(asset, amount)| Operation::SingleDeposit { asset, amount }),
+        (1..=100u128, prop::option::of(0..2usize)).prop_map(|(share, asset)| Operation::Withdraw { share, asset }),
+    ]
+}
+
+// Constant product pool with an LP token minted by the pool, the owner holds the initial LP supply
+fn setup_pool(app: &mut App) -> (Addr, Addr) {
+    let owner = Addr::unchecked("owner");
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(1_000_000_000_000, "asset1"), coin(1_000_000_000_000, "asset2")],
+    })).unwrap();
+
+    let lp_code = ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    );
+    let lp_code_id = app.store_code(Box::new(lp_code));
+    let lp_token = app.instantiate_contract(
+        lp_code_id,
+        owner.clone(),
+        &cw20_base::msg::InstantiateMsg {
+            name: "AMM LP token".to_string(),
+            symbol: "AMMLP".to_string(),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin { address: owner.to_string(), amount: Uint128::new(1_000_000) }],
+            mint: Some(MinterResponse { minter: owner.to_string(), cap: None }),
+            marketing: None,
+        },
+        &[],
+        "mock-lp-token",
+        None,
+    ).unwrap();
+
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+    let pool = app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(1_000_000, "asset1"), coin(1_000_000, "asset2")],
+            initial_lp_token_supply: Uint128::new(1_000_000),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: lp_token.to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::percent(100),
+                pool_type: PoolType::ConstantProduct,
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(1_000_000, "asset1"), coin(1_000_000, "asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap();
+
+    app.execute_contract(owner.clone(), lp_token.clone(), &Cw20ExecuteMsg::UpdateMinter { new_minter: Some(pool.to_string()) }, &[]).unwrap();
+    app.execute_contract(owner, lp_token.clone(), &Cw20ExecuteMsg::IncreaseAllowance {
+        spender: pool.to_string(),
+        amount: Uint128::MAX,
+        expires: None,
+    }, &[]).unwrap();
+
+    (pool, lp_token)
+}
+
+// Reserves and LP supply of the pool
+fn pool_state(app: &App, pool: &Addr, lp_token: &Addr) -> ([Uint128; 2], Uint128) {
+    let reserves: ReservesResponse = app.wrap().query_wasm_smart(pool, &GetReserves {}).unwrap();
+    let token_info: TokenInfoResponse = app.wrap().query_wasm_smart(lp_token, &Cw20QueryMsg::TokenInfo {}).unwrap();
+    ([reserves.reserves[0].amount, reserves.reserves[1].amount], token_info.total_supply)
+}
+
+fn k(reserves: &[Uint128; 2]) -> Uint512 {
+    Uint512::from(reserves[0]) * Uint512::from(reserves[1])
+}
+
+fn execute(app: &mut App, pool: &Addr, lp_token: &Addr, operation: &Operation) {
+    let owner = Addr::unchecked("owner");
+    // Operations the pool rejects leave it unchanged, so their errors are ignored
+    let _ = match operation {
+        Operation::Swap { offer, amount } => {
+            let offered_asset = coin(*amount, DENOMS[*offer]);
+            app.execute_contract(owner, pool.clone(), &ExecuteMsg::Swap { offered_asset: offered_asset.clone(), ask_denom: None, referral_address: None, referral_commission: None }, &[offered_asset])
+        }
+        Operation::Deposit { amounts } => {
+            let assets = vec![coin(amounts[0], DENOMS[0]), coin(amounts[1], DENOMS[1])];
+            app.execute_contract(owner, pool.clone(), &ExecuteMsg::Deposit { assets: assets.clone() }, &assets)
+        }
+        Operation::SingleDeposit { asset, amount } => {
+            let assets = vec![coin(*amount, DENOMS[*asset])];
+            app.execute_contract(owner, pool.clone(), &ExecuteMsg::Deposit { assets: assets.clone() }, &assets)
+        }
+        Operation::Withdraw { share, asset } => {
+            let balance: cw20::BalanceResponse = app.wrap().query_wasm_smart(lp_token, &Cw20QueryMsg::Balance { address: owner.to_string() }).unwrap();
+            let amount_to_burn = balance.balance.multiply_ratio(*share, 100u128);
+            app.execute_contract(owner, pool.clone(), &ExecuteMsg::Withdraw {
+                amount_to_burn,
+                asset: asset.map(|asset| DENOMS[asset].to_string()),
+                position_id: None,
+            }, &[])
+        }
+    };
+}
+
+// Concentrated liquidity pool whose only liquidity is the owner's full range position
+fn setup_concentrated_pool(app: &mut App) -> Addr {
+    let owner = Addr::unchecked("owner");
+    app.sudo(SudoMsg::Bank(BankSudo::Mint {
+        to_address: owner.to_string(),
+        amount: vec![coin(1_000_000_000_000, "asset1"), coin(1_000_000_000_000, "asset2")],
+    })).unwrap();
+
+    let code = ContractWrapper::new(
+        amm_contract::contract::execute,
+        amm_contract::contract::instantiate,
+        amm_contract::contract::query,
+    );
+    let code_id = app.store_code(Box::new(code));
+    app.instantiate_contract(
+        code_id,
+        owner.clone(),
+        &InstantiateMsg {
+            initial_funding: vec![coin(1_000_000, "asset1"), coin(4_000_000, "asset2")],
+            initial_lp_token_supply: Uint128::new(1_000_000),
+            owner: None,
+            contract_config: Config {
+                lp_token_addr: "lp_tokens".to_string(),
+                fee_share: Decimal::from_ratio(Uint128::new(3), Uint128::new(1000)),
+                tolerance_percentage: Decimal::percent(100),
+                pool_type: PoolType::Concentrated { tick_spacing: 10 },
+                position_nft_addr: None,
+                dynamic_fee: None,
+                max_referral_commission: Decimal::zero(),
+            },
+        },
+        &[coin(1_000_000, "asset1"), coin(4_000_000, "asset2")],
+        "mock-amm-contract",
+        None,
+    ).unwrap()
+}
+
+// Reserves of the pool and the amounts of the full range position at the current price, rounded up
+fn concentrated_state(app: &App, pool: &Addr) -> ([Uint128; 2], [Uint128; 2]) {
+    let reserves: ReservesResponse = app.wrap().query_wasm_smart(pool, &GetReserves {}).unwrap();
+    let state: ConcentratedPool = app.wrap().query_wasm_smart(pool, &GetConcentratedPool {}).unwrap();
+    let position: Position = app.wrap().query_wasm_smart(pool, &GetPosition { owner: Addr::unchecked("owner"), position_id: 0 }).unwrap();
+
+    let lower = sqrt_price_at_tick(position.lower_tick).unwrap();
+    let upper = sqrt_price_at_tick(position.upper_tick).unwrap();
+    let owed = [
+        asset1_delta(state.sqrt_price, upper, position.liquidity, true).unwrap(),
+        asset2_delta(lower, state.sqrt_price, position.liquidity, true).unwrap(),
+    ];
+    ([reserves.reserves[0].amount, reserves.reserves[1].amount], owed)
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    // Swaps never decrease k, and deposits and withdrawals never decrease k per LP token squared,
+    // i.e. the value backing each LP token
+    #[test]
+    fn k_never_decreases(operations in prop::collection::vec(operation(), 1..16)) {
+        let mut app = App::default();
+        let (pool, lp_token) = setup_pool(&mut app);
+
+        for operation in &operations {
+            let (reserves_before, supply_before) = pool_state(&app, &pool, &lp_token);
+            execute(&mut app, &pool, &lp_token, operation);
+            let (reserves_after, supply_after) = pool_state(&app, &pool, &lp_token);
+
+            if let Operation::Swap { .. } = operation {
+                prop_assert!(k(&reserves_after) >= k(&reserves_before), "{:?} decreased k", operation);
+            } else if !supply_after.is_zero() {
+                let supply_before = Uint512::from(supply_before);
+                let supply_after = Uint512::from(supply_after);
+                prop_assert!(
+                    k(&reserves_after) * supply_before * supply_before >= k(&reserves_before) * supply_after * supply_after,
+                    "{:?} decreased k per LP token",
+                    operation
+                );
+            }
+        }
+    }
+
+    // Swaps in either direction never leave a concentrated liquidity pool holding less than its
+    // positions are worth at the new price, i.e. the rounding of each swap step favors the pool
+    #[test]
+    fn concentrated_pool_stays_solvent(swaps in prop::collection::vec((0..2usize, 1..2_000_000u128), 1..16)) {
+        let mut app = App::default();
+        let pool = setup_concentrated_pool(&mut app);
+
+        for (offer, amount) in &swaps {
+            let offered_asset = coin(*amount, DENOMS[*offer]);
+            let _ = app.execute_contract(
+                Addr::unchecked("owner"),
+                pool.clone(),
+                &ExecuteMsg::Swap { offered_asset: offered_asset.clone(), ask_denom: None, referral_address: None, referral_commission: None },
+                &[offered_asset],
+            );
+
+            let (reserves, owed) = concentrated_state(&app, &pool);
+            prop_assert!(reserves[0] >= owed[0] && reserves[1] >= owed[1], "swap of {} {} left reserves {:?} below {:?}", amount, DENOMS[*offer], reserves, owed);
+        }
+    }
+}